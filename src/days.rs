@@ -1,21 +1,26 @@
+use std::fmt::Display;
+
 use aoc_lib::Day;
+use num::{CheckedAdd, CheckedMul, One, Zero};
+
+use crate::solution::Answer;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day19;
-mod day20;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day19;
+pub mod day20;
 
 pub static DAYS: &[Day] = &[
     day01::DAY,
@@ -35,3 +40,47 @@ pub static DAYS: &[Day] = &[
     day19::DAY,
     day20::DAY,
 ];
+
+/// Every implementation of each day's parts, keyed by day.
+pub static ANSWERS: &[(u8, &[Answer])] = &[
+    (day01::DAY.day, day01::ANSWERS),
+    (day02::DAY.day, day02::ANSWERS),
+    (day03::DAY.day, day03::ANSWERS),
+    (day04::DAY.day, day04::ANSWERS),
+    (day05::DAY.day, day05::ANSWERS),
+    (day06::DAY.day, day06::ANSWERS),
+    (day07::DAY.day, day07::ANSWERS),
+    (day08::DAY.day, day08::ANSWERS),
+    (day09::DAY.day, day09::ANSWERS),
+    (day10::DAY.day, day10::ANSWERS),
+    (day13::DAY.day, day13::ANSWERS),
+    (day14::DAY.day, day14::ANSWERS),
+    (day15::DAY.day, day15::ANSWERS),
+    (day16::DAY.day, day16::ANSWERS),
+    (day19::DAY.day, day19::ANSWERS),
+    (day20::DAY.day, day20::ANSWERS),
+];
+
+/// An answer, or a step towards one, didn't fit in the type it's computed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("answer overflowed")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+fn checked_sum<T: CheckedAdd + Zero>(items: impl IntoIterator<Item = T>) -> Result<T, Overflow> {
+    items
+        .into_iter()
+        .try_fold(T::zero(), |acc, i| acc.checked_add(&i).ok_or(Overflow))
+}
+
+fn checked_product<T: CheckedMul + One>(items: impl IntoIterator<Item = T>) -> Result<T, Overflow> {
+    items
+        .into_iter()
+        .try_fold(T::one(), |acc, i| acc.checked_mul(&i).ok_or(Overflow))
+}
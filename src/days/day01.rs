@@ -1,34 +1,49 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 1,
-    name: "Trebuchet!?",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[
-        ("Parse", run_parse),
-        ("Perfect Hash (Part 2)", run_perf_hash),
-    ],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+use aoc_lib::{Bench, BenchResult, Day, NoError};
+use color_eyre::Result;
+
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    const DAY: u8 = 1;
+    const NAME: &'static str = "Trebuchet!?";
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+    type Parsed<'a> = Vec<&'a str>;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Part2 for Day01 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data))
+    }
 }
 
+pub const DAY: Day = solution::day::<Day01>(&[
+    ("Parse", run_parse::<Day01>),
+    ("Perfect Hash (Part 2)", run_perf_hash),
+]);
+
+pub const ANSWERS: &[Answer] = &[
+    Answer::part1::<Day01>(),
+    Answer::part2::<Day01>(),
+    Answer {
+        name: "Perfect Hash (Part 2)",
+        part: 2,
+        run: |input| Ok(perf_hash(input).to_string()),
+    },
+];
+
 fn run_perf_hash(input: &str, b: Bench) -> BenchResult {
     b.bench(|| Ok::<_, NoError>(perf_hash(input)))
 }
@@ -37,7 +52,8 @@ fn parse(input: &str) -> Result<Vec<&str>> {
     Ok(input.lines().collect())
 }
 
-fn part1(lines: &[&str]) -> u16 {
+// Every line adds at most 99, so a u64 can't overflow on any input that fits in memory.
+fn part1(lines: &[&str]) -> u64 {
     lines
         .iter()
         .map(|l| {
@@ -48,15 +64,15 @@ fn part1(lines: &[&str]) -> u16 {
                 .unwrap_or_default();
             let right = bytes.rposition(|b| b.is_ascii_digit()).unwrap_or_default();
             let bytes = l.as_bytes();
-            let left = (bytes[left] - b'0') as u16;
-            let right = (bytes[right] - b'0') as u16;
+            let left = (bytes[left] - b'0') as u64;
+            let right = (bytes[right] - b'0') as u64;
 
             left * 10 + right
         })
         .sum()
 }
 
-fn part2(lines: &[&str]) -> u16 {
+fn part2(lines: &[&str]) -> u64 {
     lines
         .iter()
         .map(|line| {
@@ -67,8 +83,8 @@ fn part2(lines: &[&str]) -> u16 {
             while !bytes.is_empty() {
                 match bytes {
                     [b @ b'0'..=b'9', ..] => {
-                        left = left.or(Some((*b - b'0') as u16));
-                        right = Some((*b - b'0') as u16);
+                        left = left.or(Some((*b - b'0') as u64));
+                        right = Some((*b - b'0') as u64);
                     }
                     [b'o', b'n', b'e', ..] => {
                         left = left.or(Some(1));
@@ -116,7 +132,7 @@ fn part2(lines: &[&str]) -> u16 {
         .sum()
 }
 
-fn perf_hash(input: &str) -> u16 {
+fn perf_hash(input: &str) -> u64 {
     const LOOKUP: [u8; 256] = [
         31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31,
         31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31, 31,
@@ -144,11 +160,11 @@ fn perf_hash(input: &str) -> u16 {
                 left = None;
             }
             [b @ b'0'..=b'9', ..] => {
-                left = left.or(Some((*b - b'0') as u16));
-                right = (*b - b'0') as u16;
+                left = left.or(Some((*b - b'0') as u64));
+                right = (*b - b'0') as u64;
             }
             [a, _, b, ..] => {
-                const WORDS: [(u16, &str); 31] = [
+                const WORDS: [(u64, &str); 31] = [
                     (6, "six"),
                     (0, ""),
                     (0, ""),
@@ -234,4 +250,16 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn large_sum_test() {
+        let input = "a9b9c\n".repeat(1000);
+        let parsed = parse(&input).unwrap();
+        assert_eq!(part1(&parsed), 99_000);
+
+        let input = "nine8nine\n".repeat(1000);
+        let parsed = parse(&input).unwrap();
+        assert_eq!(part2(&parsed), 99_000);
+        assert_eq!(perf_hash(&input), 99_000);
+    }
 }
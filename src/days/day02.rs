@@ -1,46 +1,146 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 2,
-    name: "Cube Conundrum",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+use aoc_lib::Day;
+use color_eyre::Result;
+use smallvec::SmallVec;
+
+use super::{checked_product, Overflow};
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    const DAY: u8 = 2;
+    const NAME: &'static str = "Cube Conundrum";
+
+    type Parsed<'a> = Games<'a>;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+impl Part2 for Day02 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data)?)
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+pub const DAY: Day = solution::day::<Day02>(&[("Parse", run_parse::<Day02>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day02>(), Answer::part2::<Day02>()];
+
+/// Cube counts indexed by the colour IDs of a [`Games`] collection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CubeSet {
+    counts: SmallVec<[u8; 4]>,
+}
+
+impl CubeSet {
+    pub fn get(&self, colour: usize) -> u8 {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, colour: usize, count: u8) {
+        if self.counts.len() <= colour {
+            self.counts.resize(colour + 1, 0);
+        }
+        self.counts[colour] = count;
+    }
+
+    fn max(&self, other: &Self) -> Self {
+        let len = self.counts.len().max(other.counts.len());
+        CubeSet {
+            counts: (0..len).map(|c| self.get(c).max(other.get(c))).collect(),
+        }
+    }
+
+    pub fn fits_in(&self, bag: &Self) -> bool {
+        (0..self.counts.len()).all(|c| self.get(c) <= bag.get(c))
+    }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
-struct Play {
-    red: u8,
-    green: u8,
-    blue: u8,
+#[derive(Debug)]
+pub struct Game {
+    id: u32,
+    plays: Vec<CubeSet>,
+}
+
+impl Game {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn plays(&self) -> &[CubeSet] {
+        &self.plays
+    }
 }
 
 #[derive(Debug)]
-struct Game {
-    id: u8,
-    plays: Vec<Play>,
+pub struct Games<'a> {
+    colours: Vec<&'a str>,
+    games: Vec<Game>,
+}
+
+impl Games<'_> {
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    /// Colour names in order of their IDs.
+    pub fn colours(&self) -> &[&str] {
+        &self.colours
+    }
+
+    pub fn colour_id(&self, name: &str) -> Option<usize> {
+        self.colours.iter().position(|&c| c == name)
+    }
+
+    /// Builds a bag from colour names. Colours that never appear in the input are ignored, and
+    /// colours missing from `contents` are treated as having no cubes.
+    pub fn bag(&self, contents: &[(&str, u8)]) -> CubeSet {
+        let mut bag = CubeSet::default();
+        for &(name, count) in contents {
+            if let Some(id) = self.colour_id(name) {
+                bag.set(id, count);
+            }
+        }
+        bag
+    }
+
+    pub fn possible_under<'b>(&'b self, bag: &'b CubeSet) -> impl Iterator<Item = &'b Game> + 'b {
+        self.games
+            .iter()
+            .filter(|g| g.plays.iter().all(|p| p.fits_in(bag)))
+    }
+
+    pub fn minimum_bags(&self) -> impl Iterator<Item = (&Game, CubeSet)> + '_ {
+        self.games.iter().map(|g| {
+            let min_bag = g.plays.iter().fold(CubeSet::default(), |acc, p| acc.max(p));
+            (g, min_bag)
+        })
+    }
+
+    /// With enough colours, the product of the cube counts won't fit in a u64.
+    pub fn powers(&self) -> impl Iterator<Item = (&Game, Result<u64, Overflow>)> + '_ {
+        let num_colours = self.colours.len();
+        self.minimum_bags().map(move |(g, bag)| {
+            let power = checked_product((0..num_colours).map(|c| bag.get(c) as u64));
+            (g, power)
+        })
+    }
 }
 
-fn parse(input: &str) -> Result<Vec<Game>> {
-    let mut games = Vec::new();
+fn parse(input: &str) -> Result<Games<'_>> {
+    let mut games = Games {
+        colours: Vec::new(),
+        games: Vec::new(),
+    };
 
     for line in input.lines().map(str::trim) {
         let (game, playlist) = line.split_once(':').unwrap();
@@ -50,62 +150,38 @@ fn parse(input: &str) -> Result<Vec<Game>> {
         };
 
         for play_str in playlist.split(';') {
-            let mut play = Play::default();
+            let mut play = CubeSet::default();
             for dice in play_str.split(',') {
                 let (count, colour) = dice.trim().split_once(' ').unwrap();
-                let count = count.trim().parse().unwrap();
-                match colour.trim() {
-                    "red" => play.red = count,
-                    "green" => play.green = count,
-                    "blue" => play.blue = count,
-                    _ => unreachable!(),
-                }
+                let count = count.trim().parse()?;
+                let colour = colour.trim();
+                let colour_id = match games.colour_id(colour) {
+                    Some(id) => id,
+                    None => {
+                        games.colours.push(colour);
+                        games.colours.len() - 1
+                    }
+                };
+                play.set(colour_id, count);
             }
             game.plays.push(play);
         }
 
-        games.push(game);
+        games.games.push(game);
     }
 
     Ok(games)
 }
 
-fn part1(games: &[Game]) -> u16 {
-    const MAX_RED: u8 = 12;
-    const MAX_GREEN: u8 = 13;
-    const MAX_BLUE: u8 = 14;
-
-    let mut total = 0;
-
-    for game in games {
-        let total_play = game.plays.iter().fold(Play::default(), |a, p| Play {
-            red: a.red.max(p.red),
-            green: a.green.max(p.green),
-            blue: a.blue.max(p.blue),
-        });
-        if total_play.red <= MAX_RED && total_play.green <= MAX_GREEN && total_play.blue <= MAX_BLUE
-        {
-            total += game.id as u16;
-        }
-    }
-
-    total
+fn part1(games: &Games) -> u64 {
+    let bag = games.bag(&[("red", 12), ("green", 13), ("blue", 14)]);
+    games.possible_under(&bag).map(|game| game.id as u64).sum()
 }
 
-fn part2(games: &[Game]) -> u16 {
-    let mut total = 0;
-
-    for game in games {
-        let total_play = game.plays.iter().fold(Play::default(), |a, p| Play {
-            red: a.red.max(p.red),
-            green: a.green.max(p.green),
-            blue: a.blue.max(p.blue),
-        });
-
-        total += (total_play.red as u16) * (total_play.green as u16) * (total_play.blue as u16);
-    }
-
-    total
+fn part2(games: &Games) -> Result<u64, Overflow> {
+    games.powers().try_fold(0u64, |sum, (_, power)| {
+        sum.checked_add(power?).ok_or(Overflow)
+    })
 }
 
 #[cfg(test)]
@@ -135,9 +211,49 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 2286;
+        let expected = Ok(2286);
         let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn other_colours_test() {
+        let data = "Game 1: 2 red, 1 yellow; 3 blue
+                    Game 2: 5 yellow, 1 red, 1 blue
+                    Game 3: 1 red, 2 blue";
+
+        let parsed = parse(data).unwrap();
+        assert_eq!(parsed.colours, ["red", "yellow", "blue"]);
+
+        let bag = parsed.bag(&[("red", 2), ("yellow", 1), ("blue", 3)]);
+        let possible: Vec<_> = parsed.possible_under(&bag).map(|g| g.id).collect();
+        assert_eq!(possible, [1, 3]);
+
+        let powers: Vec<_> = parsed.powers().map(|(_, p)| p).collect();
+        assert_eq!(powers, [Ok(6), Ok(5), Ok(0)]);
+    }
+
+    #[test]
+    fn large_answers_test() {
+        let parsed = parse("Game 70000: 1 red\nGame 70001: 2 blue").unwrap();
+        assert_eq!(part1(&parsed), 140_001);
+
+        // 255^8 fits in a u64, 255^9 doesn't.
+        let colours = ["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+        let game = |n: usize| {
+            let play: Vec<_> = colours[..n].iter().map(|c| format!("255 {c}")).collect();
+            format!("Game 1: {}", play.join(", "))
+        };
+
+        let cases = [
+            (game(8), Ok(255u64.pow(8))),
+            (format!("{}\n{}", game(8), game(8)), Err(Overflow)),
+            (game(9), Err(Overflow)),
+        ];
+        for (input, expected) in cases {
+            let parsed = parse(&input).unwrap();
+            assert_eq!(part2(&parsed), expected, "{input}");
+        }
+    }
 }
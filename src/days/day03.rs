@@ -1,197 +1,185 @@
-use std::ops::{Add, RangeInclusive};
-
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 3,
-    name: "Gear Ratios",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+use std::collections::HashMap;
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
-}
+use aoc_lib::Day;
+use color_eyre::{eyre::eyre, Result};
+use smallvec::SmallVec;
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
-}
+use super::{checked_sum, Overflow};
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
 
-struct Schematic<'a> {
-    data: Vec<&'a [u8]>,
-    width: usize,
-}
+pub struct Day03;
 
-fn is_symbol(b: u8) -> bool {
-    !b.is_ascii_digit() && b != b'.'
+impl Solution for Day03 {
+    const DAY: u8 = 3;
+    const NAME: &'static str = "Gear Ratios";
+
+    type Parsed<'a> = Schematic;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn get_number(chunk: &[u8]) -> u32 {
-    let mut num = 0;
-    for b in chunk.iter().take_while(|b| b.is_ascii_digit()) {
-        num = num * 10 + (b - b'0') as u32;
+impl Part2 for Day03 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data)?)
     }
-    num
 }
 
-fn left_search(line: &[u8]) -> usize {
-    let (last_idx, _) = line
-        .iter()
-        .enumerate()
-        .rev()
-        .take_while(|(_, b)| b.is_ascii_digit())
-        .last()
-        .unwrap();
-    last_idx
+pub const DAY: Day = solution::day::<Day03>(&[("Parse", run_parse::<Day03>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day03>(), Answer::part2::<Day03>()];
+
+#[derive(Debug, Clone)]
+pub struct Number {
+    value: u32,
+    y: usize,
+    x_start: usize,
+    x_end: usize,
+    symbols: SmallVec<[usize; 2]>,
 }
 
-impl Schematic<'_> {
-    fn is_beside_symbol(&self, y: usize, x_range: RangeInclusive<usize>) -> bool {
-        let left_most = x_range.start().saturating_sub(1);
-        let right_most = x_range.end().add(1).min(self.width - 1);
-        // dbg!(y, left_most, right_most, self.width);
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    kind: u8,
+    y: usize,
+    x: usize,
+    numbers: SmallVec<[usize; 6]>,
+}
 
-        let same_line = self.data[y];
+impl Number {
+    pub fn value(&self) -> u32 {
+        self.value
+    }
 
-        let line_up = y.saturating_sub(1);
-        let line_down = y.add(1).min(self.data.len() - 1);
+    /// The `(x, y)` position of the first digit.
+    pub fn position(&self) -> (usize, usize) {
+        (self.x_start, self.y)
+    }
+}
 
-        let left_right_symbol = is_symbol(same_line[left_most]) | is_symbol(same_line[right_most]);
-        let up_symbol = self.data[line_up][left_most..=right_most]
-            .iter()
-            .fold(false, |acc, b| acc | is_symbol(*b));
-        let down_symbol = self.data[line_down][left_most..=right_most]
-            .iter()
-            .fold(false, |acc, b| acc | is_symbol(*b));
+impl Symbol {
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
 
-        left_right_symbol | up_symbol | down_symbol
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
     }
+}
 
-    fn get_neighbouring_numbers(&self, buff: &mut Vec<u32>, y: usize, x: usize) {
-        let same_line = self.data[y];
+/// The schematic as a bipartite graph between numbers and the symbols they touch.
+#[derive(Debug)]
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+}
 
-        // Left and right are easy because they are definitely separate.
-        if let Some(b'0'..=b'9') = same_line.get(x + 1) {
-            buff.push(get_number(&same_line[x + 1..]));
-        }
+impl Schematic {
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|n| !n.symbols.is_empty())
+    }
 
-        if let Some(b'0'..=b'9') = same_line.get(x.wrapping_sub(1)) {
-            let start_idx = left_search(&same_line[..x]);
-            buff.push(get_number(&same_line[start_idx..]));
-        }
+    pub fn symbols_with_neighbours(&self, kind: u8, count: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |s| s.kind == kind && s.numbers.len() == count)
+    }
 
-        let left_most = x.saturating_sub(1);
-        let right_most = x.add(1).min(self.width - 1);
-        let line_up = self.data[y.saturating_sub(1)];
-        let line_down = self.data[y.add(1).min(self.data.len() - 1)];
-        let a = [line_up, line_down];
-        let b = [line_down];
-        let c = [line_up];
-        let lines: &[&[u8]] = if y > 0 && y < self.data.len() {
-            &a
-        } else if y == 0 {
-            &b
-        } else {
-            &c
-        };
-        for line in lines {
-            let over_chars = &line[left_most..=right_most];
-
-            match over_chars {
-                // On a border
-                [a, _] | [_, a] if a.is_ascii_digit() => {
-                    let idx = left_most + line[1].is_ascii_digit() as usize;
-                    let start_idx = left_search(&line[..=idx]);
-                    buff.push(get_number(&line[start_idx..]));
-                }
-                // Definitely only have a single number.
-                [_, a, _] if a.is_ascii_digit() => {
-                    let start_idx = left_search(&line[..=x]);
-                    buff.push(get_number(&line[start_idx..]));
-                }
-                // Two separated numbers.
-                [a, _, b] => {
-                    if a.is_ascii_digit() {
-                        let start_idx = left_search(&line[..x]);
-                        buff.push(get_number(&line[start_idx..]));
-                    }
-                    if b.is_ascii_digit() {
-                        buff.push(get_number(&line[x + 1..]));
-                    }
-                }
-                _ => {}
-            }
-        }
+    pub fn neighbours<'a>(&'a self, symbol: &'a Symbol) -> impl Iterator<Item = &'a Number> {
+        symbol.numbers.iter().map(|&n| &self.numbers[n])
     }
 }
 
-fn parse(input: &str) -> Result<Schematic> {
-    let data: Vec<&[u8]> = input
-        .trim()
-        .lines()
-        .map(str::trim)
-        .map(str::as_bytes)
-        .collect();
-    let width = data[0].len();
-    Ok(Schematic { data, width })
+fn is_symbol(b: u8) -> bool {
+    !b.is_ascii_digit() && b != b'.'
 }
 
-fn part1(schematic: &Schematic) -> u32 {
-    let mut sum = 0;
+fn parse(input: &str) -> Result<Schematic> {
+    let mut numbers = Vec::new();
+    let mut symbols = Vec::new();
+
+    for (y, line) in input.trim().lines().map(str::trim).enumerate() {
+        let mut line_iter = line.bytes().enumerate().peekable();
+        while let Some((x, b)) = line_iter.next() {
+            if is_symbol(b) {
+                symbols.push(Symbol {
+                    kind: b,
+                    y,
+                    x,
+                    numbers: SmallVec::new(),
+                });
+                continue;
+            }
 
-    for (y, line) in schematic.data.iter().enumerate() {
-        let mut line_iter = line.iter().enumerate();
-        while let Some((x_start, &b)) = line_iter.next() {
             if !b.is_ascii_digit() {
                 continue;
             }
 
-            let mut num = (b - b'0') as u32;
-            let mut x_end = x_start;
-            while let Some((x, &n @ b'0'..=b'9')) = line_iter.next() {
-                num = num * 10 + (n - b'0') as u32;
+            let too_large = || eyre!("number at line {}, column {} is too large", y + 1, x + 1);
+            let mut value = (b - b'0') as u32;
+            let mut x_end = x;
+            while let Some((x, n @ b'0'..=b'9')) = line_iter.peek().copied() {
+                value = value
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add((n - b'0') as u32))
+                    .ok_or_else(too_large)?;
                 x_end = x;
+                line_iter.next();
             }
 
-            if schematic.is_beside_symbol(y, x_start..=x_end) {
-                sum += num;
-            }
+            numbers.push(Number {
+                value,
+                y,
+                x_start: x,
+                x_end,
+                symbols: SmallVec::new(),
+            });
         }
     }
 
-    sum
-}
-
-fn part2(schematic: &Schematic) -> u32 {
-    let mut sum = 0;
-    let mut buff = Vec::with_capacity(10);
+    let symbol_positions: HashMap<_, _> = symbols
+        .iter()
+        .enumerate()
+        .map(|(idx, s)| ((s.x, s.y), idx))
+        .collect();
 
-    for (y, line) in schematic.data.iter().enumerate() {
-        for (x, &c) in line.iter().enumerate() {
-            if c != b'*' {
-                continue;
-            }
+    for (num_idx, number) in numbers.iter_mut().enumerate() {
+        let y_range = number.y.saturating_sub(1)..=number.y + 1;
+        let x_range = number.x_start.saturating_sub(1)..=number.x_end + 1;
 
-            buff.clear();
-            schematic.get_neighbouring_numbers(&mut buff, y, x);
-            if let [a, b] = buff.as_slice() {
-                sum += a * b;
+        for y in y_range {
+            for x in x_range.clone() {
+                if let Some(&sym_idx) = symbol_positions.get(&(x, y)) {
+                    number.symbols.push(sym_idx);
+                    symbols[sym_idx].numbers.push(num_idx);
+                }
             }
         }
     }
 
-    sum
+    Ok(Schematic { numbers, symbols })
+}
+
+fn part1(schematic: &Schematic) -> u64 {
+    schematic.part_numbers().map(|n| n.value as u64).sum()
+}
+
+fn part2(schematic: &Schematic) -> Result<u64, Overflow> {
+    // The product of two u32s always fits in a u64, but the sum of them might not.
+    checked_sum(schematic.symbols_with_neighbours(b'*', 2).map(|gear| {
+        schematic
+            .neighbours(gear)
+            .map(|n| n.value as u64)
+            .product::<u64>()
+    }))
 }
 
 #[cfg(test)]
@@ -221,9 +209,159 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 467_835;
+        let expected = Ok(467_835);
         let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn symbol_graph_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 1)
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        let gears: Vec<_> = parsed
+            .symbols_with_neighbours(b'*', 2)
+            .map(|s| (s.x, s.y))
+            .collect();
+        assert_eq!(gears, [(3, 1), (5, 8)]);
+
+        let lonely: Vec<_> = parsed
+            .symbols_with_neighbours(b'*', 1)
+            .flat_map(|s| parsed.neighbours(s))
+            .map(|n| n.value)
+            .collect();
+        assert_eq!(lonely, [617]);
+    }
+
+    #[test]
+    fn edges_test() {
+        let data = "\
+            1.....2
+            *.....#
+            .......
+            ...3.$4
+            5*6....";
+
+        let parsed = parse(data).unwrap();
+        assert_eq!(part1(&parsed), 1 + 2 + 4 + 5 + 6);
+        assert_eq!(part2(&parsed), Ok(5 * 6));
+    }
+
+    /// Checks every cell around every number directly.
+    fn reference(grid: &[Vec<u8>]) -> (u64, u64) {
+        let mut numbers = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let start = x;
+                while x < row.len() && row[x].is_ascii_digit() {
+                    x += 1;
+                }
+                if start != x {
+                    let value = std::str::from_utf8(&row[start..x]).unwrap();
+                    numbers.push((y, start, x - 1, value.parse::<u64>().unwrap()));
+                } else {
+                    x += 1;
+                }
+            }
+        }
+
+        let touches = |&(y, x_start, x_end, _): &(usize, usize, usize, u64), sx, sy| {
+            sy + 1 >= y && sy <= y + 1 && sx + 1 >= x_start && sx <= x_end + 1
+        };
+
+        let mut part1 = 0;
+        let mut part2 = 0;
+        for (sy, row) in grid.iter().enumerate() {
+            for (sx, &b) in row.iter().enumerate() {
+                if b == b'*' {
+                    let adjacent: Vec<_> = numbers.iter().filter(|n| touches(n, sx, sy)).collect();
+                    if let [a, b] = adjacent[..] {
+                        part2 += a.3 * b.3;
+                    }
+                }
+            }
+        }
+
+        for number in &numbers {
+            let is_part = grid.iter().enumerate().any(|(sy, row)| {
+                row.iter()
+                    .enumerate()
+                    .any(|(sx, &b)| is_symbol(b) && touches(number, sx, sy))
+            });
+            if is_part {
+                part1 += number.3;
+            }
+        }
+
+        (part1, part2)
+    }
+
+    #[test]
+    fn random_schematics_test() {
+        // Xorshift, so the schematics are the same every run.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut rand = |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+
+        for _ in 0..2000 {
+            let width = 1 + rand(12) as usize;
+            let height = 1 + rand(8) as usize;
+
+            let grid: Vec<Vec<u8>> = (0..height)
+                .map(|_| {
+                    let mut row = Vec::with_capacity(width);
+                    let mut digits = 0;
+                    for _ in 0..width {
+                        // Keep numbers short enough to parse.
+                        let b = match rand(10) {
+                            0..=2 if digits < 3 => b'0' + rand(10) as u8,
+                            0..=5 => b'.',
+                            6..=7 => b'*',
+                            _ => b"#$+/"[rand(4) as usize],
+                        };
+                        digits = if b.is_ascii_digit() { digits + 1 } else { 0 };
+                        row.push(b);
+                    }
+                    row
+                })
+                .collect();
+
+            let input = grid
+                .iter()
+                .map(|row| std::str::from_utf8(row).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let parsed = parse(&input).unwrap();
+
+            let expected = reference(&grid);
+            assert_eq!(
+                (part1(&parsed), part2(&parsed).unwrap()),
+                expected,
+                "\n{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn large_numbers_test() {
+        let parsed = parse("4294967295*4294967295").unwrap();
+        assert_eq!(part1(&parsed), 2 * 4_294_967_295);
+        assert_eq!(part2(&parsed), Ok(4_294_967_295 * 4_294_967_295));
+
+        let gear = "4294967295*4294967295";
+        let parsed = parse(&[gear, &".".repeat(gear.len()), gear].join("\n")).unwrap();
+        assert_eq!(part2(&parsed), Err(Overflow));
+
+        let err = parse("..\n.4294967296").unwrap_err().to_string();
+        assert_eq!(err, "number at line 2, column 2 is too large");
+    }
 }
@@ -1,35 +1,74 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 4,
-    name: "Scratchcards",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+use aoc_lib::Day;
+use color_eyre::{eyre::bail, Result};
+
+use super::Overflow;
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    const DAY: u8 = 4;
+    const NAME: &'static str = "Scratchcards";
+
+    type Parsed<'a> = Vec<Card>;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data)?)
+    }
 }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+impl Part2 for Day04 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data)?)
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+pub const DAY: Day = solution::day::<Day04>(&[("Parse", run_parse::<Day04>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day04>(), Answer::part2::<Day04>()];
+
+/// Each set bit marks a number on the card, so numbers must be below 128.
+#[derive(Debug, Clone, Copy)]
+pub struct Card {
+    winning: u128,
+    have: u128,
 }
 
-#[derive(Debug, Clone)]
-struct Card {
-    winning: Vec<u8>,
-    have: Vec<u8>,
+impl Card {
+    pub fn winning(self) -> impl Iterator<Item = u8> {
+        set_numbers(self.winning)
+    }
+
+    pub fn have(self) -> impl Iterator<Item = u8> {
+        set_numbers(self.have)
+    }
+
+    pub fn num_matches(self) -> u32 {
+        (self.winning & self.have).count_ones()
+    }
+}
+
+fn set_numbers(set: u128) -> impl Iterator<Item = u8> {
+    (0..128).filter(move |&n| set & (1 << n) != 0)
+}
+
+fn parse_number_set(numbers: &str) -> Result<u128> {
+    let mut set = 0;
+    for num in numbers.split_ascii_whitespace() {
+        let num: u32 = num.parse()?;
+        if num >= 128 {
+            bail!("card number {num} is too large, must be less than 128");
+        }
+        set |= 1 << num;
+    }
+    Ok(set)
 }
 
 fn parse(input: &str) -> Result<Vec<Card>> {
@@ -39,66 +78,44 @@ fn parse(input: &str) -> Result<Vec<Card>> {
         let (_, numbers) = line["Card ".len()..].split_once(": ").unwrap();
         let (winning, have) = numbers.split_once('|').unwrap();
 
-        let winning = winning
-            .split_ascii_whitespace()
-            .map(str::parse::<u8>)
-            .collect::<Result<_, _>>()?;
-        let have = have
-            .split_ascii_whitespace()
-            .map(str::parse::<u8>)
-            .collect::<Result<_, _>>()?;
-
-        cards.push(Card { winning, have });
+        cards.push(Card {
+            winning: parse_number_set(winning)?,
+            have: parse_number_set(have)?,
+        });
     }
 
     Ok(cards)
 }
 
-fn part1(cards: &[Card]) -> u32 {
-    cards
-        .iter()
-        .map(|card| {
-            let num_matchs = card
-                .have
-                .iter()
-                .filter(|&n| card.winning.contains(n))
-                .count() as u32;
-            if num_matchs > 0 {
-                u32::pow(2, num_matchs - 1)
-            } else {
-                0
-            }
-        })
-        .sum()
+fn part1(cards: &[Card]) -> Result<u64, Overflow> {
+    cards.iter().try_fold(0u64, |sum, card| {
+        let points = match card.num_matches() {
+            0 => 0,
+            // A card can have up to 128 matches, far more doubling than a u64 holds.
+            n => 1u64.checked_shl(n - 1).ok_or(Overflow)?,
+        };
+        sum.checked_add(points).ok_or(Overflow)
+    })
 }
 
-fn part2(cards: &[Card]) -> u32 {
-    let win_values: Vec<_> = cards
-        .iter()
-        .map(|card| {
-            card.have
-                .iter()
-                .filter(|&n| card.winning.contains(n))
-                .count() as u8
-        })
-        .collect();
-
-    let mut num_cards = vec![1; cards.len()];
-    let mut sum = 0;
-
-    for (i, &win_count) in win_values.iter().enumerate() {
+fn part2(cards: &[Card]) -> Result<u64, Overflow> {
+    let mut num_cards = vec![1u64; cards.len()];
+    let mut sum = 0u64;
+
+    for (i, card) in cards.iter().enumerate() {
         let this_count = num_cards[i];
-        sum += this_count;
+        sum = sum.checked_add(this_count).ok_or(Overflow)?;
 
-        if win_count == 0 {
-            continue;
-        }
-        num_cards[i + 1..][..win_count as usize]
+        // Copies never extend past the end of the table.
+        for count in num_cards[i + 1..]
             .iter_mut()
-            .for_each(|c| *c += this_count);
+            .take(card.num_matches() as usize)
+        {
+            *count = count.checked_add(this_count).ok_or(Overflow)?;
+        }
     }
 
-    sum
+    Ok(sum)
 }
 
 #[cfg(test)]
@@ -114,7 +131,7 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 13;
+        let expected = Ok(13);
         let actual = part1(&parsed);
 
         assert_eq!(expected, actual);
@@ -128,9 +145,49 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 30;
+        let expected = Ok(30);
         let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn large_number_test() {
+        assert!(parse("Card 1: 127 1 | 127 5").is_ok());
+        assert!(parse("Card 1: 128 1 | 127 5").is_err());
+        assert!(parse("Card 1: 1 2 | 300 5").is_err());
+    }
+
+    #[test]
+    fn part2_overflowing_wins_test() {
+        let parsed = parse("Card 1: 1 2 3 | 1 2 3\nCard 2: 4 5 | 4 5").unwrap();
+        let expected = Ok(1 + 2);
+        let actual = part2(&parsed);
+
+        assert_eq!(expected, actual);
+    }
+
+    /// Card `i` of `count` wins the next `count - i` cards, so copies double with every card.
+    fn doubling_cards(count: u32) -> String {
+        (1..=count)
+            .map(|i| {
+                let numbers: Vec<_> = (1..=count - i).map(|n| n.to_string()).collect();
+                let numbers = numbers.join(" ");
+                format!("Card {i}: {numbers} | {numbers}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn large_answers_test() {
+        // 2^33 points won't fit in a u32.
+        let parsed = parse(&doubling_cards(35)).unwrap();
+        assert_eq!(part1(&parsed), Ok((1 << 34) - 1));
+        assert_eq!(part2(&parsed), Ok((1 << 35) - 1));
+
+        let parsed = parse(&doubling_cards(66)).unwrap();
+        assert_eq!(part1(&parsed), Err(Overflow));
+        assert_eq!(part2(&parsed), Err(Overflow));
+    }
 }
@@ -1,34 +1,41 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
-use aoc_lib::{misc::ArrChunks, Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 5,
-    name: "If You Give A Seed A Fertilizer",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+use aoc_lib::{misc::ArrChunks, Day};
+use color_eyre::Result;
+
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u8 = 5;
+    const NAME: &'static str = "If You Give A Seed A Fertilizer";
+
+    type Parsed<'a> = Almanac;
+    type Part1 = u64;
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Part2 for Day05 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data))
+    }
 }
 
+pub const DAY: Day = solution::day::<Day05>(&[("Parse", run_parse::<Day05>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day05>(), Answer::part2::<Day05>()];
+
 #[derive(Debug, Clone, Copy)]
 struct Id<T>(u64, PhantomData<T>);
 
@@ -135,7 +142,7 @@ impl<T, U> Map<T, U> {
 }
 
 #[derive(Debug, Default)]
-struct Almanac {
+pub struct Almanac {
     seeds: Vec<Id<Seed>>,
     seed_to_soil: Map<Seed, Soil>,
     soil_to_fertilizer: Map<Soil, Fertilizer>,
@@ -147,6 +154,21 @@ struct Almanac {
 }
 
 impl Almanac {
+    /// The numbers on the seeds line, which part 2 reads as `(start, length)` pairs.
+    pub fn seeds(&self) -> impl Iterator<Item = u64> + '_ {
+        self.seeds.iter().map(|s| s.0)
+    }
+
+    /// Follows a seed through every map to its location.
+    pub fn location_of(&self, seed: u64) -> u64 {
+        self.seed_to_location(Id::new(seed)).0
+    }
+
+    /// Follows a location back through every map to the seed that would be planted there.
+    pub fn seed_at(&self, location: u64) -> u64 {
+        self.location_to_seed(Id::new(location)).0
+    }
+
     fn seed_to_location(&self, seed: Id<Seed>) -> Id<Location> {
         let soil = self.seed_to_soil.map(seed);
         let fert = self.soil_to_fertilizer.map(soil);
@@ -1,38 +1,68 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 6,
-    name: "Wait For It",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse_p1(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+use aoc_lib::Day;
+use color_eyre::Result;
+use num::{integer::Roots, BigUint, Integer};
+
+use super::{checked_product, Overflow};
+use crate::solution::{self, run_parse, Answer, Solution, SolvePart2};
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    const DAY: u8 = 6;
+    const NAME: &'static str = "Wait For It";
+
+    type Parsed<'a> = Vec<Race<u64>>;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse_p1(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data)?)
+    }
 }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse_p2(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(data)))
+/// Part 2 reads each line as a single number, ignoring the spaces.
+impl SolvePart2 for Day06 {
+    type Parsed2<'a> = Race<BigUint>;
+    type Part2 = BigUint;
+
+    fn parse_part2(input: &str) -> Result<Self::Parsed2<'_>> {
+        parse_p2(input)
+    }
+
+    fn part2(data: &Self::Parsed2<'_>) -> Result<Self::Part2> {
+        Ok(part2(data))
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse_p1(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+pub const DAY: Day = solution::day::<Day06>(&[("Parse", run_parse::<Day06>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day06>(), Answer::part2::<Day06>()];
+
+#[derive(Debug, Clone)]
+pub struct Race<T> {
+    time: T,
+    distance: T,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Race {
-    time: u32,
-    distance: u64,
+impl<T> Race<T> {
+    pub fn new(time: T, distance: T) -> Self {
+        Self { time, distance }
+    }
+
+    pub fn time(&self) -> &T {
+        &self.time
+    }
+
+    /// The record distance to beat.
+    pub fn distance(&self) -> &T {
+        &self.distance
+    }
 }
 
-fn parse_p1(input: &str) -> Result<Vec<Race>> {
+fn parse_p1(input: &str) -> Result<Vec<Race<u64>>> {
     let input = input.trim();
     let split_pos = input.as_bytes().iter().position(|&b| b == b'\n').unwrap();
 
@@ -40,7 +70,7 @@ fn parse_p1(input: &str) -> Result<Vec<Race>> {
 
     let times = time["time: ".len()..]
         .split_ascii_whitespace()
-        .map(str::parse::<u32>);
+        .map(str::parse::<u64>);
     let distances = distance["distance: ".len()..]
         .split_ascii_whitespace()
         .map(str::parse::<u64>);
@@ -54,61 +84,71 @@ fn parse_p1(input: &str) -> Result<Vec<Race>> {
     })
 }
 
-fn parse_p2(input: &str) -> Result<Race> {
+fn parse_p2(input: &str) -> Result<Race<BigUint>> {
     let input = input.trim();
     let split_pos = input.as_bytes().iter().position(|&b| b == b'\n').unwrap();
 
     let (time, distance) = input.split_at(split_pos);
 
-    let time = time["time: ".len()..]
-        .bytes()
-        .filter(|b| b.is_ascii_digit())
-        .fold(0, |acc, b| acc * 10 + (b - b'0') as u32);
-    let distance = distance["distance: ".len()..]
-        .bytes()
-        .filter(|b| b.is_ascii_digit())
-        .fold(0, |acc, b| acc * 10 + (b - b'0') as u64);
+    let parse_kerned = |s: &str| {
+        let digits: String = s.chars().filter(char::is_ascii_digit).collect();
+        digits.parse::<BigUint>()
+    };
 
-    Ok(Race { time, distance })
+    Ok(Race {
+        time: parse_kerned(&time["time: ".len()..])?,
+        distance: parse_kerned(&distance["distance: ".len()..])?,
+    })
 }
 
-fn calc_race_distance(hold_time: u32, race_len: u32) -> u64 {
-    hold_time as u64 * (race_len - hold_time) as u64
-}
+/// Counts the hold times `h` where `h * (time - h) > distance`.
+///
+/// The winning holds lie strictly between the roots of `h^2 - time*h + distance`, so we take the
+/// integer square root of the discriminant to get a lower bound on the first winning hold, then
+/// step up to the exact boundary. The curve is symmetric about `time / 2`, which gives the upper
+/// bound for free.
+pub fn num_winning_holds<T>(race: &Race<T>) -> T
+where
+    T: Integer + Roots + Clone + From<u8>,
+{
+    let Race { time, distance } = race;
 
-fn part1(races: &[Race]) -> usize {
-    races
-        .iter()
-        .map(|r| {
-            (1..r.time)
-                .map(|i| calc_race_distance(i, r.time))
-                .filter(|&l| l > r.distance)
-                .count()
-        })
-        .product()
-}
+    let time_sq = time.clone() * time.clone();
+    let four_dist = T::from(4) * distance.clone();
+    if time_sq <= four_dist {
+        return T::zero();
+    }
 
-fn part2(race: Race) -> u32 {
-    // Let's binary search this bugger! (Thanks Silmeth!)
-    let bin_search = |f: fn(u64, u64) -> bool| {
-        let mut left = 0;
-        let mut right = race.time;
-        while (right - left) > 1 {
-            let mid = (right - left) / 2 + left;
-            let distance = calc_race_distance(mid, race.time);
-            if f(distance, race.distance) {
-                right = mid;
-            } else {
-                left = mid;
-            }
+    let root = (time_sq - four_dist).sqrt();
+    let mut lower = (time.clone() - root) / T::from(2);
+    loop {
+        if lower.clone() * T::from(2) > *time {
+            // The real interval lies between two integers.
+            return T::zero();
         }
-        (left, right)
-    };
+        if lower.clone() * (time.clone() - lower.clone()) > *distance {
+            break;
+        }
+        lower = lower + T::one();
+    }
 
-    let lower = bin_search(|a, b| a > b).1;
-    let higher = bin_search(|a, b| a < b).0;
+    let upper = time.clone() - lower.clone();
+    upper - lower + T::one()
+}
+
+fn part1(races: &[Race<u64>]) -> Result<u64, Overflow> {
+    checked_product(races.iter().map(|race| {
+        // Squaring the time needs twice the bits, but the count itself is at most the time.
+        let race = Race {
+            time: race.time as u128,
+            distance: race.distance as u128,
+        };
+        num_winning_holds(&race) as u64
+    }))
+}
 
-    higher - lower + 1
+fn part2(race: &Race<BigUint>) -> BigUint {
+    num_winning_holds(race)
 }
 
 #[cfg(test)]
@@ -124,7 +164,7 @@ mod tests {
             .unwrap();
 
         let parsed = parse_p1(&data).unwrap();
-        let expected = 288;
+        let expected = Ok(288);
         let actual = part1(&parsed);
 
         assert_eq!(expected, actual);
@@ -138,9 +178,104 @@ mod tests {
             .unwrap();
 
         let parsed = parse_p2(&data).unwrap();
-        let expected = 71503;
-        let actual = part2(parsed);
+        let expected = BigUint::from(71503u32);
+        let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    fn calc_race_distance(hold_time: u32, race_len: u32) -> u64 {
+        hold_time as u64 * (race_len - hold_time) as u64
+    }
+
+    // The binary search from the original part 2, kept as a reference.
+    fn bin_search_solve(time: u32, distance: u64) -> u32 {
+        let bin_search = |f: fn(u64, u64) -> bool| {
+            let mut left = 0;
+            let mut right = time;
+            while (right - left) > 1 {
+                let mid = (right - left) / 2 + left;
+                let race_distance = calc_race_distance(mid, time);
+                if f(race_distance, distance) {
+                    right = mid;
+                } else {
+                    left = mid;
+                }
+            }
+            (left, right)
+        };
+
+        let lower = bin_search(|a, b| a > b).1;
+        let higher = bin_search(|a, b| a < b).0;
+
+        higher - lower + 1
+    }
+
+    #[test]
+    fn closed_form_matches_bin_search_test() {
+        for time in 2..60u32 {
+            let best = calc_race_distance(time / 2, time);
+            for distance in 0..best {
+                let race = Race {
+                    time: time as u64,
+                    distance,
+                };
+                let actual = num_winning_holds(&race);
+
+                let brute_force = (0..=time)
+                    .filter(|&h| calc_race_distance(h, time) > distance)
+                    .count() as u64;
+                assert_eq!(brute_force, actual, "time: {time}, distance: {distance}");
+
+                // The binary search over-counts when a hold exactly equals the record.
+                let has_tie = (0..=time).any(|h| calc_race_distance(h, time) == distance);
+                if !has_tie {
+                    let expected = bin_search_solve(time, distance) as u64;
+                    assert_eq!(expected, actual, "time: {time}, distance: {distance}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn record_boundary_test() {
+        // Holding for 3 or 4 exactly equals the record, which doesn't win.
+        let race = Race {
+            time: 7u64,
+            distance: 12,
+        };
+        assert_eq!(num_winning_holds(&race), 0);
+
+        let race = Race {
+            time: 8u64,
+            distance: 15,
+        };
+        assert_eq!(num_winning_holds(&race), 1);
+
+        let race = Race {
+            time: 30u64,
+            distance: 200,
+        };
+        assert_eq!(num_winning_holds(&race), 9);
+    }
+
+    #[test]
+    fn big_race_test() {
+        let time: BigUint = "100000000000000000000".parse().unwrap();
+        let race = Race {
+            distance: BigUint::from(0u8),
+            time: time.clone(),
+        };
+        assert_eq!(num_winning_holds(&race), time - BigUint::from(1u8));
+    }
+
+    #[test]
+    fn part1_large_races_test() {
+        // Squaring a time of 2^40 overflows a u64.
+        let parsed = parse_p1("Time: 1099511627776\nDistance: 0").unwrap();
+        assert_eq!(part1(&parsed), Ok((1 << 40) - 1));
+
+        let parsed = parse_p1("Time: 1099511627776 1099511627776\nDistance: 0 0").unwrap();
+        assert_eq!(part1(&parsed), Err(Overflow));
+    }
 }
@@ -1,35 +1,41 @@
-use std::cmp::Ordering;
-
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 7,
-    name: "Camel Cards",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(solve(&data, Hand::p1_cmp)))
-}
+use aoc_lib::Day;
+use color_eyre::Result;
+
+use super::Overflow;
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    const DAY: u8 = 7;
+    const NAME: &'static str = "Camel Cards";
+
+    type Parsed<'a> = Vec<Play>;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(solve(&data, Hand::p2_cmp)))
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(solve(data, &PART1_RULES)?)
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Part2 for Day07 {
+    type Part2 = u64;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(solve(data, &PART2_RULES)?)
+    }
 }
 
+pub const DAY: Day = solution::day::<Day07>(&[("Parse", run_parse::<Day07>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day07>(), Answer::part2::<Day07>()];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Card {
+pub enum Card {
     Two,
     Three,
     Four,
@@ -45,23 +51,8 @@ enum Card {
     Ace,
 }
 
-impl Card {
-    fn p1_cmp(self, other: Self) -> Ordering {
-        (self as u8).cmp(&(other as u8))
-    }
-
-    fn p2_cmp(self, other: Self) -> Ordering {
-        match (self, other) {
-            (Card::Joker, Card::Joker) => Ordering::Equal,
-            (_, Card::Joker) => Ordering::Greater,
-            (Card::Joker, _) => Ordering::Less,
-            _ => (self as usize).cmp(&(other as usize)),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum Type {
+pub enum Type {
     HighCard,
     OnePair,
     TwoPair,
@@ -72,62 +63,117 @@ enum Type {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Hand([Card; 5]);
+pub struct Hand(pub [Card; 5]);
+
+/// Describes how a variant of Camel Cards ranks cards and classifies hands.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleSet {
+    /// Tie-break strength of each card, indexed by `Card as usize`.
+    strength: [u8; 13],
+    /// Cards that can stand in for any other, as a bitmask indexed by `Card as usize`.
+    wildcards: u16,
+    /// Maps the sorted card counts, with wildcards already applied, to a hand type.
+    classify: fn([u8; 13]) -> Type,
+}
 
-impl Hand {
-    fn get_type_p1(self) -> Type {
-        let mut counts = [0; 13];
-        self.0
-            .into_iter()
-            .map(|c| c as usize)
-            .for_each(|i| counts[i] += 1);
+pub const STANDARD_ORDER: [Card; 13] = [
+    Card::Two,
+    Card::Three,
+    Card::Four,
+    Card::Five,
+    Card::Six,
+    Card::Seven,
+    Card::Eight,
+    Card::Nine,
+    Card::Ten,
+    Card::Joker,
+    Card::Queen,
+    Card::King,
+    Card::Ace,
+];
+
+pub const PART1_RULES: RuleSet = RuleSet::new(STANDARD_ORDER, &[]);
+pub const PART2_RULES: RuleSet = RuleSet::new(
+    [
+        Card::Joker,
+        Card::Two,
+        Card::Three,
+        Card::Four,
+        Card::Five,
+        Card::Six,
+        Card::Seven,
+        Card::Eight,
+        Card::Nine,
+        Card::Ten,
+        Card::Queen,
+        Card::King,
+        Card::Ace,
+    ],
+    &[Card::Joker],
+);
+
+impl RuleSet {
+    /// `order` lists every card from weakest to strongest.
+    pub const fn new(order: [Card; 13], wildcards: &[Card]) -> Self {
+        let mut strength = [0; 13];
+        let mut i = 0;
+        while i < order.len() {
+            strength[order[i] as usize] = i as u8;
+            i += 1;
+        }
 
-        counts.sort_unstable();
-        count_to_type(counts)
+        let mut wild_mask = 0;
+        let mut i = 0;
+        while i < wildcards.len() {
+            wild_mask |= 1 << wildcards[i] as u16;
+            i += 1;
+        }
+
+        Self {
+            strength,
+            wildcards: wild_mask,
+            classify: count_to_type,
+        }
     }
 
-    fn p1_cmp(self, other: Self) -> Ordering {
-        self.get_type_p1().cmp(&other.get_type_p1()).then_with(|| {
-            self.0
-                .into_iter()
-                .zip(other.0)
-                .fold(Ordering::Equal, |acc, (a, b)| acc.then(a.p1_cmp(b)))
-        })
+    /// Replaces the standard hand types with `classify`, which is given the sorted card counts.
+    pub const fn with_classifier(self, classify: fn([u8; 13]) -> Type) -> Self {
+        Self { classify, ..self }
     }
 
-    fn get_type_p2(&self) -> Type {
+    fn is_wild(&self, card: Card) -> bool {
+        self.wildcards & (1 << card as u16) != 0
+    }
+
+    pub fn hand_type(&self, hand: Hand) -> Type {
         let mut counts = [0; 13];
-        self.0
-            .into_iter()
-            .map(|c| c as usize)
-            .for_each(|i| counts[i] += 1);
-
-        let count_joker = counts[Card::Joker as usize];
-        counts[Card::Joker as usize] = 0;
-
-        (0..counts.len())
-            .filter(|i| *i != Card::Joker as usize)
-            .map(|i| {
-                let mut local_counts = counts;
-                local_counts[i] += count_joker;
-                local_counts.sort_unstable();
-                count_to_type(local_counts)
-            })
-            .max()
-            .unwrap()
+        let mut num_wild = 0;
+        for card in hand.0 {
+            if self.is_wild(card) {
+                num_wild += 1;
+            } else {
+                counts[card as usize] += 1;
+            }
+        }
+
+        // Wildcards always do best joining the largest group.
+        counts.sort_unstable();
+        counts[12] += num_wild;
+        (self.classify)(counts)
     }
 
-    fn p2_cmp(self, other: Self) -> Ordering {
-        self.get_type_p2().cmp(&other.get_type_p2()).then_with(|| {
-            self.0
-                .into_iter()
-                .zip(other.0)
-                .fold(Ordering::Equal, |acc, (a, b)| acc.then(a.p2_cmp(b)))
-        })
+    /// Packs the hand type and the strength of each card into a key that orders hands by rank.
+    pub fn sort_key(&self, hand: Hand) -> u32 {
+        hand.0
+            .into_iter()
+            .fold(self.hand_type(hand) as u32, |acc, card| {
+                (acc << 4) | self.strength[card as usize] as u32
+            })
     }
 }
 
-fn count_to_type(counts: [i32; 13]) -> Type {
+/// Classifies hands under the standard rules.
+pub fn count_to_type(counts: [u8; 13]) -> Type {
     match counts {
         [.., 5] => Type::FiveOfAKind,
         [.., 4] => Type::FourOfAKind,
@@ -140,9 +186,19 @@ fn count_to_type(counts: [i32; 13]) -> Type {
 }
 
 #[derive(Debug, Clone)]
-struct Play {
+pub struct Play {
     hand: Hand,
-    bid: u16,
+    bid: u32,
+}
+
+impl Play {
+    pub fn hand(&self) -> Hand {
+        self.hand
+    }
+
+    pub fn bid(&self) -> u32 {
+        self.bid
+    }
 }
 
 fn parse_card(b: u8) -> Card {
@@ -176,22 +232,28 @@ fn parse(input: &str) -> Result<Vec<Play>> {
 
             Ok(Play {
                 hand: parse_hand(hand),
-                bid: bid.parse().unwrap(),
+                bid: bid.parse()?,
             })
         })
         .collect()
 }
 
-fn solve(plays: &[Play], sortfn: fn(Hand, Hand) -> Ordering) -> u32 {
-    let mut plays = plays.to_owned();
-    plays.sort_unstable_by(|a, b| sortfn(a.hand, b.hand));
-    let len = plays.len() as u32;
+pub fn solve(plays: &[Play], rules: &RuleSet) -> Result<u64, Overflow> {
+    let mut ranked: Vec<_> = plays
+        .iter()
+        .map(|play| (rules.sort_key(play.hand), play.bid))
+        .collect();
+    ranked.sort_unstable_by_key(|&(key, _)| key);
 
-    plays
+    ranked
         .into_iter()
-        .zip(1..=len)
-        .map(|(play, mult)| play.bid as u32 * mult)
-        .sum()
+        .zip(1u64..)
+        .try_fold(0u64, |sum, ((_, bid), rank)| {
+            (bid as u64)
+                .checked_mul(rank)
+                .and_then(|winnings| sum.checked_add(winnings))
+                .ok_or(Overflow)
+        })
 }
 
 #[cfg(test)]
@@ -213,7 +275,7 @@ mod tests {
 
         for (hand, expected_type) in tests {
             let parsed = parse_hand(hand);
-            let actual = parsed.get_type_p1();
+            let actual = PART1_RULES.hand_type(parsed);
             assert_eq!(expected_type, actual, "hand");
         }
     }
@@ -224,7 +286,10 @@ mod tests {
         for (a, b) in tests {
             let a_hand = parse_hand(a);
             let b_hand = parse_hand(b);
-            assert!(a_hand.p1_cmp(b_hand).is_gt(), "{a} > {b}");
+            assert!(
+                PART1_RULES.sort_key(a_hand) > PART1_RULES.sort_key(b_hand),
+                "{a} > {b}"
+            );
         }
     }
 
@@ -236,8 +301,8 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 6440;
-        let actual = solve(&parsed, Hand::p1_cmp);
+        let expected = Ok(6440);
+        let actual = solve(&parsed, &PART1_RULES);
 
         assert_eq!(expected, actual);
     }
@@ -255,7 +320,7 @@ mod tests {
 
         for (hand, expected_type) in tests {
             let parsed = parse_hand(hand);
-            let actual = parsed.get_type_p2();
+            let actual = PART2_RULES.hand_type(parsed);
             assert_eq!(expected_type, actual, "{hand}");
         }
     }
@@ -266,7 +331,10 @@ mod tests {
         for (a, b) in tests {
             let a_hand = parse_hand(a);
             let b_hand = parse_hand(b);
-            assert!(a_hand.p2_cmp(b_hand).is_gt(), "{a} > {b}");
+            assert!(
+                PART2_RULES.sort_key(a_hand) > PART2_RULES.sort_key(b_hand),
+                "{a} > {b}"
+            );
         }
     }
 
@@ -278,9 +346,59 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 5905;
-        let actual = solve(&parsed, Hand::p2_cmp);
+        let expected = Ok(5905);
+        let actual = solve(&parsed, &PART2_RULES);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn variant_rules_test() {
+        // Jokers and twos are both wild, and aces rank lowest.
+        let mut order = STANDARD_ORDER;
+        order.rotate_right(1);
+        let rules = RuleSet::new(order, &[Card::Joker, Card::Two]);
+
+        assert_eq!(rules.hand_type(parse_hand("2J3K4")), Type::ThreeOfAKind);
+        assert_eq!(rules.hand_type(parse_hand("22JJ7")), Type::FiveOfAKind);
+        assert!(rules.sort_key(parse_hand("KAAAA")) > rules.sort_key(parse_hand("AKKKK")));
+    }
+
+    #[test]
+    fn custom_classifier_test() {
+        // Only the largest group counts, so full houses and two pairs are no better than
+        // their largest part.
+        let rules = PART2_RULES.with_classifier(|counts| match counts[12] {
+            5 => Type::FiveOfAKind,
+            4 => Type::FourOfAKind,
+            3 => Type::ThreeOfAKind,
+            2 => Type::OnePair,
+            _ => Type::HighCard,
+        });
+
+        assert_eq!(rules.hand_type(parse_hand("23332")), Type::ThreeOfAKind);
+        assert_eq!(rules.hand_type(parse_hand("KK677")), Type::OnePair);
+        assert_eq!(rules.hand_type(parse_hand("KTJJT")), Type::FourOfAKind);
+        assert!(rules.sort_key(parse_hand("KK677")) < rules.sort_key(parse_hand("KKK23")));
+
+        let parsed = parse("23332 10\n2345A 1\n22234 100").unwrap();
+        // 2345A is lowest, then the three of a kinds tie on type and 22234 wins on cards.
+        assert_eq!(solve(&parsed, &rules), Ok(1 + 2 * 100 + 3 * 10));
+    }
+
+    #[test]
+    fn large_bids_test() {
+        let parsed = parse("32T3K 70000\nKK677 70000").unwrap();
+        assert_eq!(solve(&parsed, &PART1_RULES), Ok(70000 + 2 * 70000));
+
+        // The winnings sum to `bid * n * (n + 1) / 2`, which passes u64::MAX between these.
+        let play = parse("AAAAA 4294967295").unwrap().remove(0);
+        for (count, expected) in [
+            (90_000u64, Ok(4_294_967_295 * (90_000 * 90_001 / 2))),
+            (100_000, Err(Overflow)),
+        ] {
+            let plays = vec![play.clone(); count as usize];
+            assert_eq!(solve(&plays, &PART1_RULES), expected, "{count}");
+        }
+    }
 }
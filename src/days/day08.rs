@@ -1,34 +1,49 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-use num::Integer;
+use aoc_lib::Day;
+use color_eyre::{eyre::bail, Result};
+use num::{integer::ExtendedGcd, Integer};
 
-pub const DAY: Day = Day {
-    day: 8,
-    name: "Haunted Wasteland",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
+use crate::solution::{self, run_parse_part2, Answer, Solution, SolvePart2};
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+pub struct Day08;
+
+impl Solution for Day08 {
+    const DAY: u8 = 8;
+    const NAME: &'static str = "Haunted Wasteland";
+
+    /// The map along with its AAA and ZZZ nodes, which only part 1 needs.
+    type Parsed<'a> = (Map, NodeId, NodeId);
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        let map = parse(input)?;
+        let (start, end) = endpoints(&map)?;
+        Ok((map, start, end))
+    }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+    fn part1((map, start, end): &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(map, *start, *end)?)
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl SolvePart2 for Day08 {
+    type Parsed2<'a> = Map;
+    type Part2 = u64;
+
+    fn parse_part2(input: &str) -> Result<Self::Parsed2<'_>> {
+        parse(input)
+    }
+
+    fn part2(data: &Self::Parsed2<'_>) -> Result<Self::Part2> {
+        Ok(part2(data)?)
+    }
 }
 
+pub const DAY: Day = solution::day::<Day08>(&[("Parse", run_parse_part2::<Day08>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day08>(), Answer::part2::<Day08>()];
+
 #[derive(Debug, Clone, Copy)]
 enum Step {
     Left,
@@ -36,7 +51,7 @@ enum Step {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct NodeId(u16);
+pub struct NodeId(u16);
 
 #[derive(Debug, Clone, Copy)]
 struct Node {
@@ -48,14 +63,24 @@ struct Node {
 }
 
 #[derive(Debug, Clone)]
-struct Map {
+pub struct Map {
     steps: Vec<Step>,
-    start: NodeId,
-    end: NodeId,
+    start: Option<NodeId>,
+    end: Option<NodeId>,
     nodes: Vec<Node>,
 }
 
 impl Map {
+    /// The number of nodes, including those only referenced by another node.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of left/right instructions before they repeat.
+    pub fn num_steps(&self) -> usize {
+        self.steps.len()
+    }
+
     fn get_node_id<'a>(&mut self, name: &'a str, id_map: &mut HashMap<&'a str, NodeId>) -> NodeId {
         if let Some(&id) = id_map.get(name) {
             return id;
@@ -91,24 +116,27 @@ fn parse(input: &str) -> Result<Map> {
         .bytes()
         .map(|b| if b == b'L' { Step::Left } else { Step::Right })
         .collect();
+    if steps.is_empty() {
+        bail!("no steps in instruction list");
+    }
 
     let mut map = Map {
         steps,
-        start: NodeId(0),
-        end: NodeId(0),
+        start: None,
+        end: None,
         nodes: Vec::new(),
     };
 
     let mut interner = HashMap::new();
 
-    for line in graph.trim().lines() {
+    for line in graph.trim().lines().map(str::trim) {
         let (name, next) = line.split_once(" = ").unwrap();
 
         let name_node = map.get_node_id(name, &mut interner);
         if name == "AAA" {
-            map.start = name_node;
+            map.start = Some(name_node);
         } else if name == "ZZZ" {
-            map.end = name_node;
+            map.end = Some(name_node);
         }
 
         let (left, right) = next
@@ -128,52 +156,203 @@ fn parse(input: &str) -> Result<Map> {
     Ok(map)
 }
 
-fn part1(map: &Map) -> u32 {
-    let mut cur_id = map.start;
+/// Part 1 needs a path from AAA to ZZZ, so both must exist.
+fn endpoints(map: &Map) -> Result<(NodeId, NodeId)> {
+    let Some(start) = map.start else {
+        bail!("network has no AAA node");
+    };
+    let Some(end) = map.end else {
+        bail!("network has no ZZZ node");
+    };
+
+    Ok((start, end))
+}
+
+// The walk ends before any (node, step index) state repeats, so the count is bounded by the size
+// of `seen` and can't overflow a u64.
+fn part1(map: &Map, start: NodeId, end: NodeId) -> Result<u64, WalkError> {
+    let num_steps = map.steps.len();
+    // Once a (node, step index) state repeats, the walk is looping without reaching the end.
+    let mut seen = vec![false; map.nodes.len() * num_steps];
+
+    let mut cur_id = start;
     let mut step_count = 0;
 
-    let mut steps = map.steps.iter().cycle();
+    while cur_id != end {
+        let step_idx = step_count as usize % num_steps;
+        let state = &mut seen[cur_id.0 as usize * num_steps + step_idx];
+        if *state {
+            return Err(WalkError::GoalUnreachable);
+        }
+        *state = true;
 
-    while cur_id != map.end {
         step_count += 1;
         let node = map.get_node(cur_id);
-        cur_id = match steps.next().unwrap() {
+        cur_id = match map.steps[step_idx] {
             Step::Left => node.left,
             Step::Right => node.right,
         };
     }
 
-    step_count
+    Ok(step_count)
 }
 
-fn part2(map: &Map) -> u64 {
-    let start_nodes: Vec<_> = map.nodes.iter().filter(|n| n.end_a).map(|n| n.id).collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WalkError {
+    GoalUnreachable,
+    NoCommonStep,
+    Overflow,
+}
 
-    let mut cycle_lengths = vec![0u64; start_nodes.len()];
+impl Display for WalkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WalkError::GoalUnreachable => f.write_str("ZZZ cannot be reached from AAA"),
+            WalkError::NoCommonStep => f.write_str("ghosts never reach Z nodes on the same step"),
+            WalkError::Overflow => f.write_str("common step count doesn't fit in a u64"),
+        }
+    }
+}
+
+impl std::error::Error for WalkError {}
+
+/// The steps on which a ghost stands on a Z node, as a run-in followed by a repeating cycle.
+#[derive(Debug)]
+struct GhostPath {
+    cycle_start: u64,
+    cycle_len: u64,
+    /// Hits before the cycle starts.
+    prefix_hits: Vec<u64>,
+    /// Hits during the first lap of the cycle. These repeat every `cycle_len` steps.
+    cycle_hits: Vec<u64>,
+}
 
-    for (cycle_len, node) in cycle_lengths.iter_mut().zip(&start_nodes) {
-        let mut cur_id = *node;
+impl GhostPath {
+    fn is_hit(&self, step: u64) -> bool {
+        if step < self.cycle_start {
+            self.prefix_hits.contains(&step)
+        } else {
+            let lap_step = self.cycle_start + (step - self.cycle_start) % self.cycle_len;
+            self.cycle_hits.contains(&lap_step)
+        }
+    }
+}
 
-        let mut steps = map.steps.iter().cycle();
-        let mut step_count = 0;
+/// Walks from `start` until a (node, step index) state repeats.
+fn trace_ghost(map: &Map, start: NodeId, first_seen: &mut Vec<u64>) -> GhostPath {
+    let num_steps = map.steps.len();
+    first_seen.clear();
+    first_seen.resize(map.nodes.len() * num_steps, u64::MAX);
 
-        while !map.get_node(cur_id).end_z {
-            step_count += 1;
-            let node = map.get_node(cur_id);
-            cur_id = match steps.next().unwrap() {
-                Step::Left => node.left,
-                Step::Right => node.right,
+    let mut hits = Vec::new();
+    let mut cur_id = start;
+    let mut step_count = 0;
+
+    loop {
+        let step_idx = step_count as usize % num_steps;
+        let state = cur_id.0 as usize * num_steps + step_idx;
+
+        let seen_at = first_seen[state];
+        if seen_at != u64::MAX {
+            let cycle_hits = hits.split_off(hits.partition_point(|&h| h < seen_at));
+            return GhostPath {
+                cycle_start: seen_at,
+                cycle_len: step_count - seen_at,
+                prefix_hits: hits,
+                cycle_hits,
             };
         }
+        first_seen[state] = step_count;
 
-        *cycle_len = step_count;
+        let node = map.get_node(cur_id);
+        if node.end_z {
+            hits.push(step_count);
+        }
+
+        cur_id = match map.steps[step_idx] {
+            Step::Left => node.left,
+            Step::Right => node.right,
+        };
+        step_count += 1;
     }
+}
+
+/// Merges two congruences `x = r (mod m)`, which may have non-coprime moduli.
+fn crt_combine(
+    (r1, m1): (u64, u64),
+    (r2, m2): (u64, u64),
+) -> Result<Option<(u64, u64)>, WalkError> {
+    let (r1, m1, r2, m2) = (r1 as i128, m1 as i128, r2 as i128, m2 as i128);
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+
+    if (r2 - r1) % gcd != 0 {
+        return Ok(None);
+    }
+
+    let lcm = u64::try_from(m1 / gcd * m2).map_err(|_| WalkError::Overflow)?;
+
+    // Both factors are below `m2 / gcd`, so their product fits in a u128 and `m1 * k` below the
+    // LCM fits in a u64.
+    let n = m2 / gcd;
+    let diff = ((r2 - r1) / gcd).rem_euclid(n) as u128;
+    let k = (diff * x.rem_euclid(n) as u128 % n as u128) as i128;
+    let r = (r1 + m1 * k).rem_euclid(lcm as i128);
+
+    Ok(Some((r as u64, lcm)))
+}
+
+fn part2(map: &Map) -> Result<u64, WalkError> {
+    let mut first_seen = Vec::new();
+    let ghosts: Vec<_> = map
+        .nodes
+        .iter()
+        .filter(|n| n.end_a)
+        .map(|n| trace_ghost(map, n.id, &mut first_seen))
+        .collect();
 
-    let [first, rest @ ..] = cycle_lengths.as_slice() else {
-        unreachable!()
+    let Some(latest) = ghosts.iter().max_by_key(|g| g.cycle_start) else {
+        return Err(WalkError::NoCommonStep);
     };
 
-    rest.iter().fold(*first, |acc, r| acc.lcm(r))
+    // Any answer before every ghost is cycling must be a run-in hit of the last ghost to start.
+    if let Some(&step) = latest
+        .prefix_hits
+        .iter()
+        .find(|&&step| ghosts.iter().all(|g| g.is_hit(step)))
+    {
+        return Ok(step);
+    }
+
+    let mut congruences = vec![(0, 1)];
+    for ghost in &ghosts {
+        congruences = congruences
+            .iter()
+            .flat_map(|&c| {
+                ghost.cycle_hits.iter().filter_map(move |&h| {
+                    crt_combine(c, (h % ghost.cycle_len, ghost.cycle_len)).transpose()
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    // Each congruence gives the first step at or after the point where everyone is cycling.
+    let min_step = latest.cycle_start;
+    congruences
+        .into_iter()
+        .map(|(r, m)| {
+            if r >= min_step {
+                return Ok(r);
+            }
+            (min_step - r)
+                .div_ceil(m)
+                .checked_mul(m)
+                .and_then(|offset| offset.checked_add(r))
+                .ok_or(WalkError::Overflow)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .min()
+        .ok_or(WalkError::NoCommonStep)
 }
 
 #[cfg(test)]
@@ -183,7 +362,7 @@ mod tests {
 
     #[test]
     fn part1_test() {
-        let tests = [(1, 2), (2, 6)];
+        let tests = [(1, Ok(2)), (2, Ok(6))];
 
         for (id, expected) in tests {
             let data = aoc_lib::input(DAY.day)
@@ -192,12 +371,29 @@ mod tests {
                 .unwrap();
 
             let parsed = parse(&data).unwrap();
-            let actual = part1(&parsed);
+            let (start, end) = endpoints(&parsed).unwrap();
+            let actual = part1(&parsed, start, end);
 
             assert_eq!(expected, actual, "{id}");
         }
     }
 
+    #[test]
+    fn part1_unreachable_test() {
+        let data = "LR
+
+            AAA = (BBB, BBB)
+            BBB = (AAA, CCC)
+            CCC = (CCC, CCC)
+            ZZZ = (ZZZ, ZZZ)";
+        let parsed = parse(data).unwrap();
+        let (start, end) = endpoints(&parsed).unwrap();
+        assert_eq!(part1(&parsed, start, end), Err(WalkError::GoalUnreachable));
+
+        assert!(endpoints(&parse("L\n\nAAA = (AAA, AAA)").unwrap()).is_err());
+        assert!(endpoints(&parse("L\n\nZZZ = (ZZZ, ZZZ)").unwrap()).is_err());
+    }
+
     #[test]
     fn part2_test() {
         let data = aoc_lib::input(DAY.day)
@@ -206,9 +402,84 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 6;
+        let expected = Ok(6);
         let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn part2_unclean_cycles_test() {
+        // The first ghost hits Z on every even step from 2, the second on 1, 4, 7, ...
+        // The third passes Z once on step 1 and then loops forever without it.
+        let offset_cycles = "L
+
+            11A = (11B, 11B)
+            11B = (11Z, 11Z)
+            11Z = (11B, 11B)
+            22A = (22Z, 22Z)
+            22Z = (22B, 22B)
+            22B = (22C, 22C)
+            22C = (22Z, 22Z)";
+        let run_in_only = "L
+
+            22A = (22Z, 22Z)
+            22Z = (22B, 22B)
+            22B = (22C, 22C)
+            22C = (22Z, 22Z)
+            33A = (33Z, 33Z)
+            33Z = (33B, 33B)
+            33B = (33B, 33B)";
+        let never_aligned = "L
+
+            11A = (11B, 11B)
+            11B = (11Z, 11Z)
+            11Z = (11B, 11B)
+            22A = (22Z, 22Z)
+            22Z = (22B, 22B)
+            22B = (22Z, 22Z)";
+
+        let tests = [
+            (offset_cycles, Ok(4)),
+            (run_in_only, Ok(1)),
+            (never_aligned, Err(WalkError::NoCommonStep)),
+        ];
+
+        for (input, expected) in tests {
+            let parsed = parse(input).unwrap();
+            let actual = part2(&parsed);
+
+            assert_eq!(expected, actual, "{input}");
+        }
+    }
+
+    #[test]
+    fn part2_overflow_test() {
+        // One ghost per prime up to 53, each on a cycle that hits Z one step before returning to
+        // A. They first line up one step before the primorial, which is past u64::MAX.
+        let mut input = String::from("L\n\n");
+        for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53] {
+            let names: Vec<_> = std::iter::once(format!("{p}A"))
+                .chain((1..p - 1).map(|i| format!("{p}N{i}")))
+                .chain(std::iter::once(format!("{p}Z")))
+                .collect();
+            for (i, name) in names.iter().enumerate() {
+                let next = &names[(i + 1) % names.len()];
+                input.push_str(&format!("{name} = ({next}, {next})\n"));
+            }
+        }
+
+        let parsed = parse(&input).unwrap();
+        assert_eq!(part2(&parsed), Err(WalkError::Overflow));
+
+        assert_eq!(crt_combine((1, 1 << 40), (2, 3 << 40)), Ok(None));
+        assert_eq!(crt_combine((0, u64::MAX), (1, 2)), Err(WalkError::Overflow));
+        assert_eq!(
+            crt_combine((5, u32::MAX as u64), (7, 1 << 32)),
+            Ok(Some((
+                18_446_744_060_824_649_735,
+                18_446_744_069_414_584_320
+            )))
+        );
+    }
 }
@@ -1,33 +1,41 @@
-use std::num::ParseIntError;
-
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 9,
-    name: "Mirage Maintenance",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(solve::<false>(&data)))
-}
+use std::{borrow::Cow, fmt::Display, num::ParseIntError};
+
+use aoc_lib::Day;
+use color_eyre::Result;
+use num::{BigInt, One, Zero};
+
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    const DAY: u8 = 9;
+    const NAME: &'static str = "Mirage Maintenance";
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(solve::<true>(&data)))
+    type Parsed<'a> = Vec<Vec<i64>>;
+    type Part1 = BigInt;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        Ok(parse(input)?)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(solve(data, Direction::Forward, 1)?)
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Part2 for Day09 {
+    type Part2 = BigInt;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(solve(data, Direction::Backward, 1)?)
+    }
 }
 
+pub const DAY: Day = solution::day::<Day09>(&[("Parse", run_parse::<Day09>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day09>(), Answer::part2::<Day09>()];
+
 fn parse(input: &str) -> Result<Vec<Vec<i64>>, ParseIntError> {
     input
         .lines()
@@ -40,41 +48,160 @@ fn parse(input: &str) -> Result<Vec<Vec<i64>>, ParseIntError> {
         .collect()
 }
 
-fn solve<const ISP2: bool>(data: &[Vec<i64>]) -> i64 {
-    let mut diffs: Vec<i64> = Vec::new();
-    let mut last_nums = Vec::new();
-    let mut ans = 0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
 
-    for set in data {
-        diffs.clear();
-        last_nums.clear();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoZeroRow {
+    line: usize,
+}
 
-        diffs.extend(set);
+impl Display for NoZeroRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sequence {} never reaches an all-zero difference row",
+            self.line + 1
+        )
+    }
+}
 
-        while diffs.iter().any(|&i| i != 0) {
-            if ISP2 {
-                last_nums.push(diffs[0]);
-            }
+impl std::error::Error for NoZeroRow {}
 
-            for (j, i) in (1..diffs.len()).zip(0..) {
-                diffs[i] = diffs[j] - diffs[i];
-            }
+/// A sequence's polynomial in Newton form, `f(x) = sum(coeffs[j] * binomial(x, j))`, where
+/// `x = 0` is the first value in the sequence.
+#[derive(Debug, Clone)]
+pub struct NewtonPoly {
+    coeffs: Coeffs,
+    len: usize,
+}
+
+/// Coefficients stay in an `i128` unless a difference overflows it.
+#[derive(Debug, Clone)]
+enum Coeffs {
+    Small(Vec<i128>),
+    Big(Vec<BigInt>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FitError {
+    NoZeroRow,
+    Overflow,
+}
+
+/// The leading values of each difference row, up to the all-zero row.
+fn difference_coeffs<T: Zero + Clone>(
+    mut diffs: Vec<T>,
+    sub: impl Fn(&T, &T) -> Option<T>,
+) -> Result<Vec<T>, FitError> {
+    let mut coeffs = Vec::new();
+
+    while diffs.iter().any(|i| !i.is_zero()) {
+        if diffs.len() == 1 {
+            return Err(FitError::NoZeroRow);
+        }
 
-            if ISP2 {
-                diffs.pop();
-            } else {
-                last_nums.push(diffs.pop().unwrap());
+        coeffs.push(diffs[0].clone());
+        for i in 0..diffs.len() - 1 {
+            diffs[i] = sub(&diffs[i + 1], &diffs[i]).ok_or(FitError::Overflow)?;
+        }
+        diffs.pop();
+    }
+
+    Ok(coeffs)
+}
+
+impl NewtonPoly {
+    pub fn fit(seq: &[i64]) -> Option<Self> {
+        let small = seq.iter().map(|&i| i as i128).collect();
+        let coeffs = match difference_coeffs(small, |a, b| a.checked_sub(*b)) {
+            Ok(coeffs) => Coeffs::Small(coeffs),
+            Err(FitError::NoZeroRow) => return None,
+            Err(FitError::Overflow) => {
+                let big = seq.iter().map(|&i| BigInt::from(i)).collect();
+                Coeffs::Big(difference_coeffs(big, |a, b| Some(a - b)).ok()?)
             }
+        };
+
+        Some(Self {
+            coeffs,
+            len: seq.len(),
+        })
+    }
+
+    /// Evaluates the polynomial without allocating, if every step fits in an `i128`.
+    fn at_small(&self, x: i64) -> Option<i128> {
+        let Coeffs::Small(coeffs) = &self.coeffs else {
+            return None;
+        };
+
+        let x = x as i128;
+        let mut binomial = 1i128;
+        let mut sum = 0i128;
+
+        for (j, &coeff) in coeffs.iter().enumerate() {
+            sum = sum.checked_add(coeff.checked_mul(binomial)?)?;
+            let j = j as i128;
+            binomial = binomial.checked_mul(x - j)? / (j + 1);
         }
 
-        if ISP2 {
-            ans += last_nums.iter().rfold(0, |acc, next| next - acc);
-        } else {
-            ans += last_nums.iter().rfold(0, |acc, next| acc + next);
+        Some(sum)
+    }
+
+    pub fn at(&self, x: i64) -> BigInt {
+        if let Some(value) = self.at_small(x) {
+            return value.into();
+        }
+
+        let coeffs: Cow<[BigInt]> = match &self.coeffs {
+            Coeffs::Small(coeffs) => coeffs.iter().map(|&c| BigInt::from(c)).collect(),
+            Coeffs::Big(coeffs) => Cow::Borrowed(coeffs),
+        };
+
+        let x = BigInt::from(x);
+        let mut binomial = BigInt::one();
+        let mut sum = BigInt::zero();
+
+        for (j, coeff) in coeffs.iter().enumerate() {
+            sum += coeff * &binomial;
+            // binomial(x, j + 1) = binomial(x, j) * (x - j) / (j + 1), which always divides exactly.
+            binomial = binomial * (&x - j) / (j + 1);
+        }
+
+        sum
+    }
+
+    fn x_beyond(&self, direction: Direction, steps: u32) -> i64 {
+        match direction {
+            Direction::Forward => self.len as i64 - 1 + steps as i64,
+            Direction::Backward => -(steps as i64),
+        }
+    }
+
+    /// Predicts the value `steps` places beyond the end of the sequence in the given direction.
+    pub fn extrapolate(&self, direction: Direction, steps: u32) -> BigInt {
+        self.at(self.x_beyond(direction, steps))
+    }
+}
+
+pub fn solve(data: &[Vec<i64>], direction: Direction, steps: u32) -> Result<BigInt, NoZeroRow> {
+    // Predictions are summed in an `i128` where they fit, and only the rest in a `BigInt`.
+    let mut small_sum = 0i128;
+    let mut big_sum = BigInt::zero();
+
+    for (line, set) in data.iter().enumerate() {
+        let poly = NewtonPoly::fit(set).ok_or(NoZeroRow { line })?;
+        let x = poly.x_beyond(direction, steps);
+        match poly.at_small(x).and_then(|v| small_sum.checked_add(v)) {
+            Some(sum) => small_sum = sum,
+            None => big_sum += poly.at(x),
         }
     }
 
-    ans
+    Ok(big_sum + small_sum)
 }
 
 #[cfg(test)]
@@ -90,8 +217,8 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 114;
-        let actual = solve::<false>(&parsed);
+        let expected = Ok(BigInt::from(114));
+        let actual = solve(&parsed, Direction::Forward, 1);
 
         assert_eq!(expected, actual);
     }
@@ -104,9 +231,59 @@ mod tests {
             .unwrap();
 
         let parsed = parse(&data).unwrap();
-        let expected = 2;
-        let actual = solve::<true>(&parsed);
+        let expected = Ok(BigInt::from(2));
+        let actual = solve(&parsed, Direction::Backward, 1);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn extrapolate_steps_test() {
+        // ... -4 5 [10 13 16 21 30 45] 68 101 146 ...
+        let poly = NewtonPoly::fit(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(poly.extrapolate(Direction::Forward, 2), BigInt::from(101));
+        assert_eq!(poly.extrapolate(Direction::Forward, 3), BigInt::from(146));
+        assert_eq!(poly.extrapolate(Direction::Backward, 2), BigInt::from(-4));
+    }
+
+    #[test]
+    fn big_extrapolation_test() {
+        // The prediction overflows an i128, so it has to fall back to a BigInt.
+        let seq: Vec<i64> = (0..5).map(|i| i * i * i * 1_000_000_000_000).collect();
+        let poly = NewtonPoly::fit(&seq).unwrap();
+        assert!(matches!(poly.coeffs, Coeffs::Small(_)));
+
+        let steps = 4_000_000_000;
+        let x = BigInt::from(4 + steps);
+        let expected = &x * &x * &x * 1_000_000_000_000i64;
+        assert_eq!(poly.extrapolate(Direction::Forward, steps), expected);
+
+        // Sums that overflow still add up, along with ones that don't.
+        let data = [seq.clone(), vec![1, 2, 3], seq];
+        let expected = &expected * 2 + (3 + steps);
+        assert_eq!(solve(&data, Direction::Forward, steps), Ok(expected));
+    }
+
+    #[test]
+    fn fit_overflow_test() {
+        let sub = |a: &i8, b: &i8| a.checked_sub(*b);
+        assert_eq!(difference_coeffs(vec![1, 3, 5], sub), Ok(vec![1, 2]));
+        assert_eq!(
+            difference_coeffs(vec![100, -100, 100], sub),
+            Err(FitError::Overflow)
+        );
+        assert_eq!(
+            difference_coeffs(vec![1, 2, 4], sub),
+            Err(FitError::NoZeroRow)
+        );
+    }
+
+    #[test]
+    fn no_zero_row_test() {
+        let data = [vec![1, 2, 3], vec![1, 2, 4, 8]];
+        assert_eq!(
+            solve(&data, Direction::Forward, 1),
+            Err(NoZeroRow { line: 1 })
+        );
+    }
 }
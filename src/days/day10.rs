@@ -1,27 +1,52 @@
 use std::collections::BinaryHeap;
 
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
+use aoc_lib::{Bench, BenchResult, Day, NoError, UserError};
+use color_eyre::{eyre::bail, Result};
 use smallvec::SmallVec;
 
-pub const DAY: Day = Day {
-    day: 10,
-    name: "Pipe Maze",
-    part_1: run_part1,
-    part_2: None,
-    other: &[("Parse", run_parse)],
-};
+use crate::solution::{self, run_parse, Answer, Solution};
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+    const NAME: &'static str = "Pipe Maze";
+
+    type Parsed<'a> = Map;
+    type Part1 = u16;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        part1(data)
+    }
+}
+
+pub const DAY: Day = solution::part1_only::<Day10>(&[
+    ("Parse", run_parse::<Day10>),
+    ("Part 1 Dijkstra", run_part1_dijkstra),
+    ("Start Shape", run_start_shape),
+]);
+
+pub const ANSWERS: &[Answer] = &[
+    Answer::part1::<Day10>(),
+    Answer {
+        name: "Part 1 Dijkstra",
+        part: 1,
+        run: |input| Ok(part1_dijkstra(&parse(input)?).to_string()),
+    },
+];
+
+fn run_part1_dijkstra(input: &str, b: Bench) -> BenchResult {
     let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+    b.bench(|| Ok::<_, NoError>(part1_dijkstra(&data)))
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+fn run_start_shape(input: &str, b: Bench) -> BenchResult {
+    let data = parse(input).map_err(UserError)?;
+    b.bench(|| Ok::<_, NoError>(data.start_shape()))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,12 +57,39 @@ enum Direction {
     West = 0b1000,
 }
 
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+
+    fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Pipe {
     dir_map: u8,
 }
 
 impl Pipe {
+    const SHAPES: [Pipe; 6] = [
+        Pipe { dir_map: 0b0011 },
+        Pipe { dir_map: 0b1100 },
+        Pipe { dir_map: 0b0101 },
+        Pipe { dir_map: 0b1001 },
+        Pipe { dir_map: 0b1010 },
+        Pipe { dir_map: 0b0110 },
+    ];
+
     fn has_dir(self, dir: Direction) -> bool {
         (self.dir_map & dir as u8) != 0
     }
@@ -56,11 +108,13 @@ impl Pipe {
 }
 
 #[derive(Debug, Clone)]
-struct Map {
+pub struct Map {
     pipes: Vec<Pipe>,
     width: u8,
     height: u8,
     start: Point,
+    /// The shape of the pipe hidden under the start tile.
+    start_shape: Pipe,
 }
 
 impl Map {}
@@ -76,6 +130,17 @@ impl Point {
         Self { x, y }
     }
 
+    fn step(self, dir: Direction, max_width: u8, max_height: u8) -> Option<Point> {
+        let (x, y) = match dir {
+            Direction::North => (self.x, self.y.checked_sub(1)?),
+            Direction::South => (self.x, self.y + 1),
+            Direction::East => (self.x + 1, self.y),
+            Direction::West => (self.x.checked_sub(1)?, self.y),
+        };
+
+        (x < max_width && y < max_height).then_some(Point::new(x, y))
+    }
+
     fn neighbours(self, max_width: u8, max_height: u8) -> SmallVec<[Point; 4]> {
         [(0xff, 0), (1, 0), (0, 0xff), (0, 1)]
             .into_iter()
@@ -99,6 +164,29 @@ impl Point {
 }
 
 impl Map {
+    /// The pipe hidden under the start tile, drawn as in the puzzle input.
+    pub fn start_shape(&self) -> char {
+        self.start_shape.render()
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// The `(x, y)` position of the start tile.
+    pub fn start(&self) -> (u8, u8) {
+        (self.start.x, self.start.y)
+    }
+
+    /// The length of the loop through the start tile, if there is one.
+    pub fn loop_len(&self) -> Option<usize> {
+        self.find_loop().map(|path| path.len())
+    }
+
     fn connect_dir(&mut self, dir: Direction, point: Point) {
         let idx = self.idx_of(point);
         self.pipes[idx].dir_map |= dir as u8;
@@ -139,6 +227,35 @@ impl Map {
         self.get(from).has_dir(from_dir) && self.get(to).has_dir(to_dir)
     }
 
+    /// Follows the pipes leaving `start` in `dir` until they lead back to `start`, returning
+    /// every point visited in order, starting with `start` itself.
+    fn walk_loop(&self, mut dir: Direction) -> Option<Vec<Point>> {
+        let mut path = vec![self.start];
+        let mut pos = self.start;
+
+        loop {
+            pos = pos.step(dir, self.width, self.height)?;
+            let pipe = self.get(pos);
+            if !pipe.has_dir(dir.opposite()) {
+                return None;
+            }
+            if pos == self.start {
+                return Some(path);
+            }
+
+            path.push(pos);
+            let exits = pipe.dir_map & !(dir.opposite() as u8);
+            dir = Direction::ALL.into_iter().find(|&d| exits == d as u8)?;
+        }
+    }
+
+    /// The loop of pipes running through the start tile.
+    fn find_loop(&self) -> Option<Vec<Point>> {
+        let start = self.get(self.start);
+        let dir = Direction::ALL.into_iter().find(|&dir| start.has_dir(dir))?;
+        self.walk_loop(dir)
+    }
+
     #[allow(unused)]
     fn render(&self) {
         for row in self.pipes.chunks_exact(self.width as usize) {
@@ -175,17 +292,26 @@ impl PartialOrd for SearchState {
 }
 
 fn parse(input: &str) -> Result<Map> {
-    let width = input.lines().next().unwrap().len();
+    let input = input.trim();
+    let Some(width) = input.lines().next().map(|l| l.trim().len()) else {
+        bail!("empty map");
+    };
     let height = input.lines().count();
 
     let mut map = Map {
         pipes: vec![Pipe { dir_map: 0 }; width * height],
         width: width as u8,
-        height: width as u8,
+        height: height as u8,
         start: Point::new(0, 0),
+        start_shape: Pipe { dir_map: 0 },
     };
+    let mut start = None;
+
+    for (y, line) in input.lines().map(str::trim).enumerate() {
+        if line.len() != width {
+            bail!("line {} is {} wide, expected {width}", y + 1, line.len());
+        }
 
-    for (y, line) in input.lines().enumerate() {
         for (x, c) in line.bytes().enumerate() {
             let point = Point::new(x as u8, y as u8);
             match c {
@@ -213,47 +339,52 @@ fn parse(input: &str) -> Result<Map> {
                     map.connect_dir(Direction::South, point);
                     map.connect_dir(Direction::East, point);
                 }
-                b'S' => {
-                    map.start = point;
-                }
+                b'S' if start.is_some() => bail!("more than one start tile"),
+                b'S' => start = Some(point),
                 _ => {}
             }
         }
     }
 
-    // Need to connect up the start.
-    if map.start.x > 0 {
-        let west = Point::new(map.start.x - 1, map.start.y);
-        if map.get(west).has_dir(Direction::East) {
-            map.connect_dir(Direction::West, map.start);
+    let Some(start) = start else {
+        bail!("no start tile");
+    };
+    map.start = start;
+
+    // Try every shape under the start, keeping those that close a loop through it.
+    let start_idx = map.idx_of(start);
+    let shapes: SmallVec<[Pipe; 6]> = Pipe::SHAPES
+        .into_iter()
+        .filter(|&shape| {
+            map.pipes[start_idx] = shape;
+            map.find_loop().is_some()
+        })
+        .collect();
+
+    let shape = match shapes[..] {
+        [shape] => shape,
+        [] => bail!("start tile at {start:?} is not part of any loop"),
+        _ => {
+            let shapes: String = shapes.iter().map(|s| s.render()).collect();
+            bail!("start tile at {start:?} could be any of `{shapes}`");
         }
-    }
+    };
 
-    if map.start.x < map.width - 1 {
-        let east = Point::new(map.start.x + 1, map.start.y);
-        if map.get(east).has_dir(Direction::West) {
-            map.connect_dir(Direction::East, map.start);
-        }
-    }
+    map.pipes[start_idx] = shape;
+    map.start_shape = shape;
 
-    if map.start.y > 0 {
-        let north = Point::new(map.start.x, map.start.y - 1);
-        if map.get(north).has_dir(Direction::South) {
-            map.connect_dir(Direction::North, map.start);
-        }
-    }
+    Ok(map)
+}
 
-    if map.start.y < map.height - 1 {
-        let south = Point::new(map.start.x, map.start.y + 1);
-        if map.get(south).has_dir(Direction::North) {
-            map.connect_dir(Direction::South, map.start);
-        }
-    }
+fn part1(map: &Map) -> Result<u16> {
+    let Some(pipe_loop) = map.find_loop() else {
+        bail!("no loop runs through the start tile");
+    };
 
-    Ok(map)
+    Ok((pipe_loop.len() / 2) as u16)
 }
 
-fn part1(map: &Map) -> u16 {
+fn part1_dijkstra(map: &Map) -> u16 {
     let mut queue = BinaryHeap::new();
     let mut dist = vec![u16::MAX; map.width as usize * map.height as usize];
 
@@ -299,9 +430,10 @@ mod tests {
         let parsed = parse(&data).unwrap();
         parsed.render();
         let expected = 4;
-        let actual = part1(&parsed);
+        let actual = part1(&parsed).unwrap();
 
         assert_eq!(expected, actual);
+        assert_eq!(expected, part1_dijkstra(&parsed));
     }
 
     #[test]
@@ -314,8 +446,72 @@ mod tests {
         let parsed = parse(&data).unwrap();
         parsed.render();
         let expected = 8;
-        let actual = part1(&parsed);
+        let actual = part1(&parsed).unwrap();
 
         assert_eq!(expected, actual);
+        assert_eq!(expected, part1_dijkstra(&parsed));
+    }
+
+    #[test]
+    fn find_loop_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 1)
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        let actual = parsed.find_loop().unwrap();
+        let expected = [
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (3, 2),
+            (3, 1),
+            (2, 1),
+        ]
+        .map(|(x, y)| Point::new(x, y));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn start_shape_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 2)
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        assert_eq!(parsed.start_shape(), 'F');
+
+        // The `|` above the start points into it, but runs off the map.
+        let parsed = parse(
+            "-||F7
+             7S-7|
+             L|7||
+             -L-J|
+             L|-JF",
+        )
+        .unwrap();
+        assert_eq!(parsed.start_shape(), 'F');
+        assert_eq!(part1(&parsed).unwrap(), 4);
+    }
+
+    #[test]
+    fn start_errors_test() {
+        let cases = [
+            ("F-7\n|.|\nL-J", "no start tile"),
+            ("S-7\n|.|\nL-S", "more than one start tile"),
+            ("...\n.S.\n...", "not part of any loop"),
+            ("F-7..\n|.|..\nL-S-7\n..|.|\n..L-J", "could be any of `JF`"),
+            ("F-7\n|S\nL-J", "line 2 is 2 wide"),
+        ];
+
+        for (input, expected) in cases {
+            let err = parse(input).unwrap_err().to_string();
+            assert!(err.contains(expected), "{input:?}: {err}");
+        }
     }
 }
@@ -1,137 +1,172 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 13,
-    name: "Point of Incidence",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+use aoc_lib::Day;
+use color_eyre::Result;
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
-}
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u8 = 13;
+    const NAME: &'static str = "Point of Incidence";
+
+    type Parsed<'a> = Vec<Block>;
+    type Part1 = usize;
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(solve(data, 0))
+    }
 }
 
-struct Block {
-    by_row: Vec<u32>,
-    // This is by_row rotated 90 degrees anticlockwise then reversed
-    by_col: Vec<u32>,
+impl Part2 for Day13 {
+    type Part2 = usize;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(solve(data, 1))
+    }
 }
 
-fn parse(input: &str) -> Result<Vec<Block>> {
-    let mut blocks = Vec::new();
+pub const DAY: Day = solution::day::<Day13>(&[("Parse", run_parse::<Day13>)]);
 
-    for block in input.split("\n\n").map(str::trim) {
-        let mut by_row = Vec::new();
-        let mut width = 0;
-        for line in block.lines() {
-            let v = line
-                .as_bytes()
-                .iter()
-                .fold(0, |acc, b| (acc << 1) | (*b == b'.') as u32);
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day13>(), Answer::part2::<Day13>()];
 
-            width = line.len();
+/// Rows of a block packed into bits, with as many words per row as the width needs.
+#[derive(Debug, Clone)]
+pub struct BitRows {
+    words_per_row: usize,
+    words: Vec<u64>,
+}
 
-            by_row.push(v);
+impl BitRows {
+    fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        Self {
+            words_per_row,
+            words: vec![0; words_per_row * height],
         }
+    }
 
-        let mut by_col = Vec::new();
-        for bit in 0..width {
-            let v = by_row.iter().fold(0, |acc, r| {
-                let bit = r & (1 << bit);
-                (acc << 1) | (bit != 0) as u32
-            });
+    pub fn len(&self) -> usize {
+        self.words.len() / self.words_per_row.max(1)
+    }
 
-            by_col.push(v);
-        }
-        by_col.reverse();
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        blocks.push(Block { by_row, by_col });
+    /// The row's cells as bits, set where the cell is ash.
+    pub fn row(&self, idx: usize) -> &[u64] {
+        &self.words[idx * self.words_per_row..][..self.words_per_row]
     }
 
-    Ok(blocks)
+    fn set(&mut self, row: usize, bit: usize) {
+        self.words[row * self.words_per_row + bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// Number of differing cells between two rows.
+    pub fn diff(&self, a: usize, b: usize) -> u32 {
+        self.row(a)
+            .iter()
+            .zip(self.row(b))
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
 }
 
-fn mirror_search_p1(vals: &[u32]) -> Option<usize> {
-    for row in 1..vals.len() {
-        // There'll always been at least one in each group.
-        let (upper, lower) = vals.split_at(row);
+pub struct Block {
+    by_row: BitRows,
+    // The transpose of by_row, so column searches are row searches.
+    by_col: BitRows,
+}
 
-        // Trim to the same length
-        let len = upper.len().min(lower.len());
-        let upper = &upper[upper.len() - len..];
-        let lower = &lower[..len];
+impl Block {
+    pub fn rows(&self) -> &BitRows {
+        &self.by_row
+    }
 
-        if upper.iter().rev().eq(lower) {
-            return Some(row);
-        }
+    /// The block transposed, so each column is a row.
+    pub fn columns(&self) -> &BitRows {
+        &self.by_col
     }
-    None
 }
 
-fn part1(blocks: &[Block]) -> usize {
-    let mut sum = 0;
+fn parse(input: &str) -> Result<Vec<Block>> {
+    let mut blocks = Vec::new();
 
-    for block in blocks {
-        if let Some(r) = mirror_search_p1(&block.by_row) {
-            sum += r * 100;
+    for block in input.split("\n\n").map(str::trim) {
+        let lines: Vec<_> = block.lines().map(str::trim).map(str::as_bytes).collect();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
+
+        let mut by_row = BitRows::new(width, height);
+        let mut by_col = BitRows::new(height, width);
+        for (y, line) in lines.iter().enumerate() {
+            for (x, _) in line.iter().enumerate().filter(|(_, &b)| b == b'.') {
+                by_row.set(y, x);
+                by_col.set(x, y);
+            }
         }
 
-        if let Some(c) = mirror_search_p1(&block.by_col) {
-            sum += c;
-        }
+        blocks.push(Block { by_row, by_col });
     }
 
-    sum
+    Ok(blocks)
 }
 
-fn mirror_search_p2(vals: &[u32]) -> Option<usize> {
-    for row in 1..vals.len() {
-        // There'll always been at least one in each group.
-        let (upper, lower) = vals.split_at(row);
-
+/// Finds the first reflection line where exactly `smudges` cells differ from their mirror.
+///
+/// If `positions` is given, it's filled with the `(row, bit)` of each smudge on the side before
+/// the line.
+pub fn mirror_search(
+    vals: &BitRows,
+    smudges: u32,
+    positions: Option<&mut Vec<(usize, usize)>>,
+) -> Option<usize> {
+    let mirrored_rows = |row: usize| {
         // Trim to the same length
-        let len = upper.len().min(lower.len());
-        let upper = &upper[upper.len() - len..];
-        let lower = &lower[..len];
-
-        let diff: u32 = upper
-            .iter()
-            .rev()
-            .zip(lower)
-            .map(|(u, l)| (u ^ l).count_ones())
-            .sum();
-
-        if diff == 1 {
-            return Some(row);
+        let len = row.min(vals.len() - row);
+        (row - len..row).rev().zip(row..row + len)
+    };
+
+    let line = (1..vals.len()).find(|&row| {
+        let mut diff = 0;
+        for (u, l) in mirrored_rows(row) {
+            diff += vals.diff(u, l);
+            if diff > smudges {
+                return false;
+            }
+        }
+        diff == smudges
+    })?;
+
+    if let Some(positions) = positions {
+        positions.clear();
+        for (u, l) in mirrored_rows(line) {
+            for (word_idx, (a, b)) in vals.row(u).iter().zip(vals.row(l)).enumerate() {
+                let mut diff = a ^ b;
+                while diff != 0 {
+                    positions.push((u, word_idx * 64 + diff.trailing_zeros() as usize));
+                    diff &= diff - 1;
+                }
+            }
         }
     }
-    None
+
+    Some(line)
 }
 
-fn part2(blocks: &[Block]) -> usize {
+fn solve(blocks: &[Block], smudges: u32) -> usize {
     let mut sum = 0;
 
     for block in blocks {
-        if let Some(r) = mirror_search_p2(&block.by_row) {
+        if let Some(r) = mirror_search(&block.by_row, smudges, None) {
             sum += r * 100;
         }
 
-        if let Some(c) = mirror_search_p2(&block.by_col) {
+        if let Some(c) = mirror_search(&block.by_col, smudges, None) {
             sum += c;
         }
     }
@@ -153,7 +188,7 @@ mod tests {
 
         let parsed = parse(&data).unwrap();
         let expected = 405;
-        let actual = part1(&parsed);
+        let actual = solve(&parsed, 0);
 
         assert_eq!(expected, actual);
     }
@@ -167,7 +202,7 @@ mod tests {
 
         let parsed = parse(&data).unwrap();
         let expected = 3;
-        let actual = part1(&parsed);
+        let actual = solve(&parsed, 0);
 
         assert_eq!(expected, actual);
     }
@@ -181,7 +216,7 @@ mod tests {
 
         let parsed = parse(&data).unwrap();
         let expected = 709;
-        let actual = part1(&parsed);
+        let actual = solve(&parsed, 0);
 
         assert_eq!(expected, actual);
     }
@@ -195,7 +230,7 @@ mod tests {
 
         let parsed = parse(&data).unwrap();
         let expected = 6;
-        let actual = part1(&parsed);
+        let actual = solve(&parsed, 0);
 
         assert_eq!(expected, actual);
     }
@@ -209,8 +244,131 @@ mod tests {
 
         let parsed = parse(&data).unwrap();
         let expected = 400;
-        let actual = part2(&parsed);
+        let actual = solve(&parsed, 1);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn smudge_positions_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 1)
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        let rows = &parsed[0].by_row;
+        let mut positions = Vec::new();
+        assert_eq!(mirror_search(rows, 1, Some(&mut positions)), Some(3));
+        assert_eq!(positions, [(0, 0)]);
+
+        // The second block has no vertical line, but does once two smudges are allowed.
+        let cols = &parsed[1].by_col;
+        assert_eq!(mirror_search(cols, 0, None), None);
+        assert!(mirror_search(cols, 2, None).is_some());
+    }
+
+    #[test]
+    fn wide_block_test() {
+        // A 64-wide block mirrored after column 33, plus its 64-tall transpose.
+        let mut seed = 0x2545_f491_u32;
+        let mut rows = Vec::new();
+        for _ in 0..5 {
+            let mut right: Vec<u8> = (0..31)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    if seed & 0x10000 == 0 {
+                        b'.'
+                    } else {
+                        b'#'
+                    }
+                })
+                .collect();
+            let mut row = vec![b'#', b'.'];
+            row.extend(right.iter().rev());
+            row.append(&mut right);
+            rows.push(row);
+        }
+
+        let wide: Vec<_> = rows
+            .iter()
+            .map(|r| std::str::from_utf8(r).unwrap())
+            .collect();
+        let tall: Vec<String> = (0..64)
+            .map(|x| rows.iter().map(|r| r[x] as char).collect())
+            .collect();
+        let input = format!("{}\n\n{}", wide.join("\n"), tall.join("\n"));
+
+        let parsed = parse(&input).unwrap();
+        let expected = 33 + 3300;
+        let actual = solve(&parsed, 0);
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn multi_word_block_test() {
+        let mut seed = 0x9e37_79b9_u32;
+        let mut random_row = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    if seed & 0x10000 == 0 {
+                        b'.'
+                    } else {
+                        b'#'
+                    }
+                })
+                .collect()
+        };
+
+        // 100 wide, mirrored after column 70, so each row spans two words.
+        let mut wide: Vec<Vec<u8>> = (0..5)
+            .map(|_| {
+                let mut row = random_row(40);
+                let mid = random_row(30);
+                row.extend(&mid);
+                row.extend(mid.iter().rev());
+                row
+            })
+            .collect();
+
+        // 100 wide, mirrored after row 3.
+        let half: Vec<_> = (0..3).map(|_| random_row(100)).collect();
+        let mut tall: Vec<Vec<u8>> = half.iter().chain(half.iter().rev()).cloned().collect();
+
+        let to_input = |blocks: [&[Vec<u8>]; 2]| {
+            blocks
+                .map(|rows| {
+                    let rows: Vec<_> = rows
+                        .iter()
+                        .map(|r| std::str::from_utf8(r).unwrap())
+                        .collect();
+                    rows.join("\n")
+                })
+                .join("\n\n")
+        };
+
+        let parsed = parse(&to_input([&wide, &tall])).unwrap();
+        assert_eq!(solve(&parsed, 0), 70 + 300);
+
+        // Column 95 mirrors column 44, and row 1 mirrors row 4.
+        wide[2][95] ^= b'.' ^ b'#';
+        tall[4][90] ^= b'.' ^ b'#';
+        let parsed = parse(&to_input([&wide, &tall])).unwrap();
+        assert_eq!(solve(&parsed, 0), 0);
+        assert_eq!(solve(&parsed, 1), 70 + 300);
+
+        let mut positions = Vec::new();
+        assert_eq!(
+            mirror_search(&parsed[0].by_col, 1, Some(&mut positions)),
+            Some(70)
+        );
+        assert_eq!(positions, [(44, 2)]);
+        assert_eq!(
+            mirror_search(&parsed[1].by_row, 1, Some(&mut positions)),
+            Some(3)
+        );
+        assert_eq!(positions, [(1, 90)]);
+    }
 }
@@ -1,28 +1,203 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, fmt::Display};
 
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
+use aoc_lib::{Bench, BenchResult, Day, NoError, UserError};
+use color_eyre::{eyre::bail, Result};
 
-pub const DAY: Day = Day {
-    day: 14,
-    name: "Parabolic Reflector Dish",
-    part_1: run_part1,
-    part_2: None,
-    other: &[("Parse", run_parse)],
-};
+use crate::solution::{self, run_parse, Answer, Solution};
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u8 = 14;
+    const NAME: &'static str = "Parabolic Reflector Dish";
+
+    type Parsed<'a> = Grid;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
+}
+
+pub const DAY: Day = solution::part1_only::<Day14>(&[
+    ("Parse", run_parse::<Day14>),
+    ("Part 1 Sets", run_part1_sets),
+    ("Loads", run_loads),
+]);
+
+pub const ANSWERS: &[Answer] = &[
+    Answer::part1::<Day14>(),
+    Answer {
+        name: "Part 1 Sets",
+        part: 1,
+        run: |input| Ok(part1_sets(&Map::from_grid(&parse(input)?)).to_string()),
+    },
+];
+
+fn run_part1_sets(input: &str, b: Bench) -> BenchResult {
+    let data = Map::from_grid(&parse(input).map_err(UserError)?);
+    b.bench(|| Ok::<_, NoError>(part1_sets(&data)))
+}
+
+fn run_loads(input: &str, b: Bench) -> BenchResult {
     let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+    b.bench(|| Ok::<_, NoError>(loads(&data)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Empty,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    tiles: Vec<Tile>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    /// The number of lanes running in the given direction, and how long each is.
+    fn lanes(&self, dir: Direction) -> (usize, usize) {
+        match dir {
+            Direction::North | Direction::South => (self.width, self.height),
+            Direction::East | Direction::West => (self.height, self.width),
+        }
+    }
+
+    /// The index of the `i`th tile along a lane, counting from the edge `dir` points at.
+    fn lane_idx(&self, dir: Direction, lane: usize, i: usize) -> usize {
+        let (x, y) = match dir {
+            Direction::North => (lane, i),
+            Direction::South => (lane, self.height - 1 - i),
+            Direction::West => (i, lane),
+            Direction::East => (self.width - 1 - i, lane),
+        };
+        y * self.width + x
+    }
+
+    /// Rolls every round rock as far as it'll go in the given direction.
+    pub fn tilt(&mut self, dir: Direction) {
+        let (num_lanes, lane_len) = self.lanes(dir);
+        for lane in 0..num_lanes {
+            let mut next_free = 0;
+            for i in 0..lane_len {
+                let idx = self.lane_idx(dir, lane, i);
+                match self.tiles[idx] {
+                    Tile::Empty => {}
+                    Tile::Square => next_free = i + 1,
+                    Tile::Round => {
+                        self.tiles[idx] = Tile::Empty;
+                        let free_idx = self.lane_idx(dir, lane, next_free);
+                        self.tiles[free_idx] = Tile::Round;
+                        next_free += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The load on the support beams at the edge `dir` points at.
+    pub fn load(&self, dir: Direction) -> u64 {
+        let (num_lanes, lane_len) = self.lanes(dir);
+        let mut load = 0;
+        for lane in 0..num_lanes {
+            for i in 0..lane_len {
+                if self.tiles[self.lane_idx(dir, lane, i)] == Tile::Round {
+                    load += (lane_len - i) as u64;
+                }
+            }
+        }
+        load
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.tiles.chunks_exact(self.width) {
+            for tile in row {
+                let c = match tile {
+                    Tile::Empty => '.',
+                    Tile::Round => 'O',
+                    Tile::Square => '#',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
+fn parse(input: &str) -> Result<Grid> {
+    let input = input.trim();
+    let Some(width) = input.lines().next().map(|l| l.trim().len()) else {
+        bail!("empty grid");
+    };
+
+    let mut tiles = Vec::new();
+    for (y, line) in input.lines().map(str::trim).enumerate() {
+        if line.len() != width {
+            bail!("line {} is {} wide, expected {width}", y + 1, line.len());
+        }
+
+        for b in line.bytes() {
+            tiles.push(match b {
+                b'.' => Tile::Empty,
+                b'O' => Tile::Round,
+                b'#' => Tile::Square,
+                _ => bail!("unexpected tile `{}` on line {}", b as char, y + 1),
+            });
+        }
+    }
+
+    Ok(Grid {
+        height: tiles.len() / width,
+        tiles,
+        width,
     })
 }
 
+fn part1(grid: &Grid) -> u64 {
+    let mut grid = grid.clone();
+    grid.tilt(Direction::North);
+    grid.load(Direction::North)
+}
+
+/// The load on each edge after tilting the platform towards it.
+fn loads(grid: &Grid) -> String {
+    let mut out = String::new();
+    for dir in Direction::ALL {
+        let mut grid = grid.clone();
+        grid.tilt(dir);
+        out.push_str(&format!("{dir:?}: {}\n", grid.load(dir)));
+    }
+    out
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Point {
     y: u8,
@@ -49,33 +224,33 @@ struct Map {
     height: u8,
 }
 
-fn parse(input: &str) -> Result<Map> {
-    let mut squares = BTreeSet::new();
-    let mut rounds = Vec::new();
-    let mut height = 0;
+impl Map {
+    fn from_grid(grid: &Grid) -> Self {
+        let mut squares = BTreeSet::new();
+        let mut rounds = Vec::new();
 
-    for (line, y) in input.trim().lines().map(str::trim).zip(0..) {
-        for (b, x) in line.bytes().zip(0..) {
-            let p = Point { x, y };
-            match b {
-                b'#' => {
-                    squares.insert(p);
+        for (row, y) in grid.tiles.chunks_exact(grid.width).zip(0..) {
+            for (&tile, x) in row.iter().zip(0..) {
+                let p = Point { x, y };
+                match tile {
+                    Tile::Square => {
+                        squares.insert(p);
+                    }
+                    Tile::Round => rounds.push(p),
+                    Tile::Empty => {}
                 }
-                b'O' => rounds.push(p),
-                _ => {}
             }
         }
-        height = y;
-    }
 
-    Ok(Map {
-        squares,
-        rounds,
-        height,
-    })
+        Map {
+            squares,
+            rounds,
+            height: grid.height as u8 - 1,
+        }
+    }
 }
 
-fn part1(map: &Map) -> u32 {
+fn part1_sets(map: &Map) -> u64 {
     let mut rounds = BTreeSet::new();
     let mut load = 0;
 
@@ -91,7 +266,7 @@ fn part1(map: &Map) -> u32 {
 
         rounds.insert(pos);
 
-        load += (map.height + 1 - pos.y) as u32;
+        load += (map.height + 1 - pos.y) as u64;
     }
 
     load
@@ -114,5 +289,93 @@ mod tests {
         let actual = part1(&parsed);
 
         assert_eq!(expected, actual);
+        assert_eq!(expected, part1_sets(&Map::from_grid(&parsed)));
+    }
+
+    /// Tilts by nudging every rock one step at a time until nothing moves.
+    fn tilt_slowly(grid: &mut Grid, dir: Direction) {
+        let (dx, dy) = match dir {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+        };
+
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for y in 0..grid.height as isize {
+                for x in 0..grid.width as isize {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if !(0..grid.width as isize).contains(&nx)
+                        || !(0..grid.height as isize).contains(&ny)
+                    {
+                        continue;
+                    }
+
+                    let idx = (y * grid.width as isize + x) as usize;
+                    let next = (ny * grid.width as isize + nx) as usize;
+                    if grid.tiles[idx] == Tile::Round && grid.tiles[next] == Tile::Empty {
+                        grid.tiles.swap(idx, next);
+                        moved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tilt_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 1)
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        let mut north = parsed.clone();
+        north.tilt(Direction::North);
+        let expected = "\
+            OOOO.#.O..
+            OO..#....#
+            OO..O##..O
+            O..#.OO...
+            ........#.
+            ..#....#.#
+            ..O..#.O.O
+            ..O.......
+            #....###..
+            #....#....";
+        assert_eq!(north, parse(expected).unwrap(), "\n{north}");
+
+        for dir in Direction::ALL {
+            let mut fast = parsed.clone();
+            fast.tilt(dir);
+            let mut slow = parsed.clone();
+            tilt_slowly(&mut slow, dir);
+            assert_eq!(fast, slow, "{dir:?}\n{fast}");
+
+            let expected_load: usize = (0..slow.tiles.len())
+                .filter(|&i| slow.tiles[i] == Tile::Round)
+                .map(|i| {
+                    let (x, y) = (i % slow.width, i / slow.width);
+                    match dir {
+                        Direction::North => slow.height - y,
+                        Direction::South => y + 1,
+                        Direction::West => slow.width - x,
+                        Direction::East => x + 1,
+                    }
+                })
+                .sum();
+            assert_eq!(fast.load(dir), expected_load as u64, "{dir:?}");
+        }
+    }
+
+    #[test]
+    fn large_load_test() {
+        // A full 2100x2100 platform has a load of 2100 * (1 + 2 + ... + 2100), past u32::MAX.
+        let row = "O".repeat(2100);
+        let input = vec![row.as_str(); 2100].join("\n");
+        let parsed = parse(&input).unwrap();
+        assert_eq!(part1(&parsed), 2100 * (2100 * 2101 / 2));
     }
 }
@@ -1,20 +1,52 @@
-use aoc_lib::{Bench, BenchResult, Day, NoError};
+use std::fmt::{Display, Write};
+
+use aoc_lib::{Bench, BenchResult, Day, NoError, UserError};
+use color_eyre::{eyre::bail, Result};
+use itertools::{EitherOrBoth, Itertools};
 use smallvec::SmallVec;
 
-pub const DAY: Day = Day {
-    day: 15,
-    name: "Lens Library",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[],
-};
+use crate::solution::{self, run_parse_part2, Answer, Solution, SolvePart2};
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u8 = 15;
+    const NAME: &'static str = "Lens Library";
+
+    /// Part 1 hashes the raw steps, so only part 2 parses them into operations.
+    type Parsed<'a> = &'a str;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        Ok(input.trim())
+    }
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| Ok::<_, NoError>(part1(input.trim())))
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| Ok::<_, NoError>(part2(input.trim())))
+impl SolvePart2 for Day15 {
+    type Parsed2<'a> = Vec<Op<'a>>;
+    type Part2 = u64;
+
+    fn parse_part2(input: &str) -> Result<Self::Parsed2<'_>> {
+        parse(input)
+    }
+
+    fn part2(data: &Self::Parsed2<'_>) -> Result<Self::Part2> {
+        Ok(part2(data))
+    }
+}
+
+pub const DAY: Day =
+    solution::day::<Day15>(&[("Parse", run_parse_part2::<Day15>), ("Trace", run_trace)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day15>(), Answer::part2::<Day15>()];
+
+fn run_trace(input: &str, b: Bench) -> BenchResult {
+    let data = parse(input).map_err(UserError)?;
+    b.bench(|| Ok::<_, NoError>(trace(&data)))
 }
 
 fn hash(s: &[u8]) -> u32 {
@@ -23,8 +55,54 @@ fn hash(s: &[u8]) -> u32 {
         & 0xff
 }
 
-fn part1(input: &str) -> u32 {
-    input.as_bytes().split(|&b| b == b',').map(hash).sum()
+fn part1(input: &str) -> u64 {
+    input
+        .as_bytes()
+        .split(|&b| b == b',')
+        .map(|step| hash(step) as u64)
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op<'a> {
+    Insert(&'a [u8], u8),
+    Remove(&'a [u8]),
+}
+
+impl Display for Op<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Insert(label, focal) => write!(f, "{}={focal}", label.escape_ascii()),
+            Op::Remove(label) => write!(f, "{}-", label.escape_ascii()),
+        }
+    }
+}
+
+/// Parses the steps into operations, as part 2 reads them.
+pub fn parse(input: &str) -> Result<Vec<Op<'_>>> {
+    let mut ops = Vec::new();
+
+    for (step, num) in input.trim().split(',').map(str::trim).zip(1..) {
+        let Some(op_pos) = step.find(['-', '=']) else {
+            bail!("step {num} (`{step}`) has no operation");
+        };
+        let (label, op_val) = step.split_at(op_pos);
+
+        if label.is_empty() || !label.bytes().all(|b| b.is_ascii_alphabetic()) {
+            bail!("step {num} (`{step}`) has an invalid label");
+        }
+        let label = label.as_bytes();
+
+        let op = match op_val.as_bytes() {
+            [b'-'] => Op::Remove(label),
+            [b'=', focal @ b'1'..=b'9'] => Op::Insert(label, focal - b'0'),
+            [b'=', ..] => bail!("step {num} (`{step}`) has an invalid focal length"),
+            _ => bail!("step {num} (`{step}`) has trailing data after `-`"),
+        };
+        ops.push(op);
+    }
+
+    Ok(ops)
 }
 
 #[derive(Debug)]
@@ -38,6 +116,25 @@ struct Map<'a> {
     boxes: Vec<SmallVec<[BoxContent<'a>; 8]>>,
 }
 
+/// Lists the non-empty boxes in the same format as the puzzle's worked example.
+impl Display for Map<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (box_id, box_) in self.boxes.iter().enumerate() {
+            if box_.is_empty() {
+                continue;
+            }
+
+            write!(f, "Box {box_id}:")?;
+            for slot in box_ {
+                write!(f, " [{} {}]", slot.key.escape_ascii(), slot.value)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl<'a> Map<'a> {
     fn new() -> Self {
         Self {
@@ -73,21 +170,54 @@ impl<'a> Map<'a> {
     }
 }
 
-fn part2(input: &str) -> u32 {
-    let mut map = Map::new();
-    input.as_bytes().split(|&b| b == b',').for_each(|v| {
-        let op_pos = v.iter().position(|&b| b == b'-' || b == b'=').unwrap();
-        let (key, op_val) = v.split_at(op_pos);
-
-        match op_val {
-            [b'-'] => map.remove(key),
-            [b'=', lens @ ..] => {
-                let value = lens.iter().fold(0, |acc, b| (acc * 10) + (b & 0xf));
-                map.insert(key, value);
-            }
-            _ => unreachable!(),
+impl<'a> Map<'a> {
+    fn apply(&mut self, op: Op<'a>) {
+        match op {
+            Op::Insert(key, value) => self.insert(key, value),
+            Op::Remove(key) => self.remove(key),
         }
-    });
+    }
+}
+
+/// Runs the operations, recording the box state after each one.
+pub fn trace(ops: &[Op]) -> String {
+    let mut map = Map::new();
+    let mut trace = String::new();
+
+    for &op in ops {
+        map.apply(op);
+        // Writing to a String can't fail.
+        let _ = writeln!(trace, "After \"{op}\":\n{map}");
+    }
+
+    trace
+}
+
+/// Compares a trace against an expected one, such as a saved copy of [`trace`]'s output, and
+/// reports the first line where they differ. Trailing whitespace is ignored.
+pub fn diff_trace(actual: &str, expected: &str) -> Option<String> {
+    let actual_lines = actual.trim_end().lines().map(str::trim_end);
+    let expected_lines = expected.trim_end().lines().map(str::trim_end);
+
+    actual_lines
+        .zip_longest(expected_lines)
+        .zip(1..)
+        .find_map(|(lines, line_num)| {
+            let (a, e) = match lines {
+                EitherOrBoth::Both(a, e) if a == e => return None,
+                EitherOrBoth::Both(a, e) => (a, e),
+                EitherOrBoth::Left(a) => (a, "<end>"),
+                EitherOrBoth::Right(e) => ("<end>", e),
+            };
+            Some(format!("line {line_num}: expected {e:?}, got {a:?}"))
+        })
+}
+
+fn part2(ops: &[Op]) -> u64 {
+    let mut map = Map::new();
+    for &op in ops {
+        map.apply(op);
+    }
 
     let mut sum = 0;
     for (box_, box_id) in map.boxes.into_iter().zip(1..) {
@@ -96,7 +226,7 @@ fn part2(input: &str) -> u32 {
         }
 
         for (slot, slot_id) in box_.into_iter().zip(1..) {
-            sum += box_id * slot_id * slot.value as u32;
+            sum += box_id * slot_id * slot.value as u64;
         }
     }
 
@@ -128,9 +258,100 @@ mod tests {
             .open()
             .unwrap();
 
+        let parsed = parse(&data).unwrap();
         let expected = 145;
-        let actual = part2(data.trim());
+        let actual = part2(&parsed);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn trace_test() {
+        // The expected trace is the box listing after each step of the part 2 worked example,
+        // copied from the puzzle text.
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 1)
+            .open()
+            .unwrap();
+        let expected = aoc_lib::input(DAY.day)
+            .example(Example::Part2, "trace")
+            .open()
+            .unwrap();
+
+        let parsed = parse(&data).unwrap();
+        let actual = trace(&parsed);
+
+        if let Some(diff) = diff_trace(&actual, &expected) {
+            panic!("{diff}");
+        }
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        assert_eq!(
+            parse(" rn=1 , cm-\n").unwrap(),
+            [Op::Insert(b"rn", 1), Op::Remove(b"cm")]
+        );
+
+        for bad in [
+            "rn",
+            "=1",
+            "r1=1",
+            "rn=0",
+            "rn=12",
+            "rn=x",
+            "rn-1",
+            "rn=1,,cm-",
+        ] {
+            assert!(parse(bad).is_err(), "{bad}");
+        }
+
+        let err = parse("rn=1,cm-,qp=").unwrap_err();
+        assert!(err.to_string().starts_with("step 3"), "{err}");
+
+        // Part 1 only hashes the steps, so it doesn't care whether they're valid operations.
+        let steps = Day15::parse("rn=0,cm-").unwrap();
+        assert_eq!(Day15::part1(&steps).unwrap(), part1("rn=0,cm-"));
+        assert!(Day15::parse_part2("rn=0,cm-").is_err());
+    }
+
+    #[test]
+    fn large_focusing_power_test() {
+        // Enough distinct labels in the last box push the focusing power past u32::MAX.
+        let labels: Vec<String> = (0u32..)
+            .map(|i| {
+                let mut i = i;
+                let bytes = [0; 5].map(|_: u8| {
+                    let b = b'a' + (i % 26) as u8;
+                    i /= 26;
+                    b
+                });
+                String::from_utf8(bytes.to_vec()).unwrap()
+            })
+            .filter(|label| hash(label.as_bytes()) == 255)
+            .take(2000)
+            .collect();
+
+        let input: Vec<_> = labels.iter().map(|l| format!("{l}=9")).collect();
+        let input = input.join(",");
+        let parsed = parse(&input).unwrap();
+        assert_eq!(part2(&parsed), 256 * 9 * (2000 * 2001 / 2));
+    }
+
+    #[test]
+    fn diff_trace_test() {
+        assert_eq!(diff_trace("a\nb  \n", "a\nb"), None);
+        assert_eq!(
+            diff_trace("a\nc", "a\nb"),
+            Some(r#"line 2: expected "b", got "c""#.to_owned())
+        );
+        assert_eq!(
+            diff_trace("a", "a\nb"),
+            Some(r#"line 2: expected "b", got "<end>""#.to_owned())
+        );
+        assert_eq!(
+            diff_trace("a\nb", "a"),
+            Some(r#"line 2: expected "<end>", got "b""#.to_owned())
+        );
+    }
 }
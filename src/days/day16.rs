@@ -1,33 +1,40 @@
 use std::collections::HashSet;
 
-use aoc_lib::{Bench, BenchResult, Day, NoError, ParseResult, UserError};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 16,
-    name: "The Floor Will Be Lava",
-    part_1: run_part1,
-    part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
-
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+use aoc_lib::Day;
+use color_eyre::Result;
+
+use crate::solution::{self, run_parse, Answer, Part2, Solution};
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u8 = 16;
+    const NAME: &'static str = "The Floor Will Be Lava";
+
+    type Parsed<'a> = Map;
+    type Part1 = u32;
 
-fn run_part2(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part2(&data)))
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Part2 for Day16 {
+    type Part2 = u32;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2> {
+        Ok(part2(data))
+    }
 }
 
+pub const DAY: Day = solution::day::<Day16>(&[("Parse", run_parse::<Day16>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day16>(), Answer::part2::<Day16>()];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Point {
     x: u8,
@@ -74,13 +81,21 @@ enum TileType {
     DiagRight, // '/'
 }
 
-struct Map {
+pub struct Map {
     tiles: Vec<TileType>,
     width: u8,
     height: u8,
 }
 
 impl Map {
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
     fn contains(&self, p: Point) -> bool {
         (p.x < self.width) & (p.y < self.height)
     }
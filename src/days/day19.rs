@@ -1,28 +1,48 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
 
 use aoc_lib::{
     misc::{IdGen, IdType},
-    Bench, BenchResult, Day, NoError, ParseResult, UserError,
-};
-use color_eyre::{Report, Result};
-
-pub const DAY: Day = Day {
-    day: 19,
-    name: "Aplenty",
-    part_1: run_part1,
-    part_2: None,
-    other: &[("Parse", run_parse)],
+    Bench, BenchResult, Day, NoError,
 };
+use color_eyre::{eyre::bail, Result};
+
+use crate::solution::{self, run_parse, Answer, Solution};
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u8 = 19;
+    const NAME: &'static str = "Aplenty";
+
+    type Parsed<'a> = System;
+    type Part1 = u64;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data))
+    }
 }
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
+pub const DAY: Day =
+    solution::part1_only::<Day19>(&[("Parse", run_parse::<Day19>), ("Validate", run_validate)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day19>()];
+
+fn run_validate(input: &str, b: Bench) -> BenchResult {
+    let (workflows_str, _) = input.split_once("\n\n").unwrap();
+    let (workflows, start) = parse_workflows(workflows_str);
     b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
+        let report: String = validate(&workflows, start)
+            .iter()
+            .map(|d| format!("{d}\n"))
+            .collect();
+        Ok::<_, NoError>(report)
     })
 }
 
@@ -57,7 +77,7 @@ enum RuleCondition {
 }
 
 #[derive(Debug, Clone, Copy)]
-struct WorkFlowId(usize);
+pub struct WorkFlowId(usize);
 impl IdType for WorkFlowId {
     fn from_usize(i: usize) -> Self {
         Self(i)
@@ -82,39 +102,234 @@ struct Rule {
 }
 
 #[derive(Debug, Default)]
-struct WorkFlow {
+pub struct WorkFlow<'a> {
+    name: &'a str,
+    // Referenced workflows get an entry even if they're never defined.
+    defined: bool,
     rules: Vec<Rule>,
 }
 
+impl<'a> WorkFlow<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Whether the input defines this workflow, rather than only sending parts to it.
+    pub fn is_defined(&self) -> bool {
+        self.defined
+    }
+}
+
 #[derive(Debug)]
-struct WorkFlows {
-    flows: Vec<WorkFlow>,
+pub struct WorkFlows<'a> {
+    flows: Vec<WorkFlow<'a>>,
 }
 
-impl Index<WorkFlowId> for WorkFlows {
-    type Output = WorkFlow;
+impl<'a> WorkFlows<'a> {
+    /// The names of the workflows defined in the input, in the order they first appear.
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.flows.iter().filter(|f| f.defined).map(|f| f.name)
+    }
+}
+
+impl<'a> Index<WorkFlowId> for WorkFlows<'a> {
+    type Output = WorkFlow<'a>;
 
     fn index(&self, index: WorkFlowId) -> &Self::Output {
         &self.flows[index.0]
     }
 }
 
-fn parse(input: &str) -> Result<(WorkFlows, Vec<Part>, WorkFlowId)> {
-    let (workflows_str, parts_str) = input.split_once("\n\n").unwrap();
+#[derive(Debug, Clone, Copy)]
+enum Decision {
+    Accept,
+    Reject,
+    /// Goes to `less` if the part's field is below `threshold`, otherwise to `rest`.
+    Split {
+        field: PartField,
+        threshold: u32,
+        less: usize,
+        rest: usize,
+    },
+}
+
+/// The workflows flattened from `in` into a single tree of comparisons.
+#[derive(Debug)]
+struct DecisionTree {
+    nodes: Vec<Decision>,
+    root: usize,
+}
+
+impl DecisionTree {
+    const ACCEPT: usize = 0;
+    const REJECT: usize = 1;
+
+    fn accepts(&self, part: &Part) -> bool {
+        let mut cur = self.root;
+        loop {
+            match self.nodes[cur] {
+                Decision::Accept => return true,
+                Decision::Reject => return false,
+                Decision::Split {
+                    field,
+                    threshold,
+                    less,
+                    rest,
+                } => cur = if part[field] < threshold { less } else { rest },
+            }
+        }
+    }
+}
+
+/// Inclusive bounds on each field of the parts that can reach a given point in the workflows.
+type FieldRanges = [(u32, u32); 4];
+
+struct Compiler<'w, 'a> {
+    workflows: &'w WorkFlows<'a>,
+    nodes: Vec<Decision>,
+    in_progress: Vec<bool>,
+}
+
+impl Compiler<'_, '_> {
+    fn compile_output(&mut self, output: RuleOutput, ranges: FieldRanges) -> Result<usize> {
+        let id = match output {
+            RuleOutput::Accept => return Ok(DecisionTree::ACCEPT),
+            RuleOutput::Reject => return Ok(DecisionTree::REJECT),
+            RuleOutput::Workflow(id) => id,
+        };
+
+        let flow = &self.workflows[id];
+        if !flow.defined {
+            bail!("workflow `{}` is referenced but never defined", flow.name);
+        }
+        if self.in_progress[id.0] {
+            bail!("workflows can loop through `{}`", flow.name);
+        }
+
+        self.in_progress[id.0] = true;
+        let node = self.compile_rules(id, 0, ranges)?;
+        self.in_progress[id.0] = false;
+
+        Ok(node)
+    }
+
+    fn compile_rules(
+        &mut self,
+        id: WorkFlowId,
+        rule_idx: usize,
+        mut ranges: FieldRanges,
+    ) -> Result<usize> {
+        let flow = &self.workflows[id];
+        let Some(&rule) = flow.rules.get(rule_idx) else {
+            bail!("workflow `{}` has no fallback rule", flow.name);
+        };
+
+        // Greater-than becomes less-than with the branches swapped.
+        let (field, threshold, pass_is_less) = match rule.cond {
+            RuleCondition::None => return self.compile_output(rule.output, ranges),
+            RuleCondition::Less(f, i) => (f, i as u32, true),
+            RuleCondition::Greater(f, i) => (f, i as u32 + 1, false),
+        };
+
+        let (lo, hi) = ranges[field as usize];
+        if hi < threshold {
+            return if pass_is_less {
+                self.compile_output(rule.output, ranges)
+            } else {
+                self.compile_rules(id, rule_idx + 1, ranges)
+            };
+        }
+        if lo >= threshold {
+            return if pass_is_less {
+                self.compile_rules(id, rule_idx + 1, ranges)
+            } else {
+                self.compile_output(rule.output, ranges)
+            };
+        }
+
+        ranges[field as usize] = (lo, threshold - 1);
+        let less = if pass_is_less {
+            self.compile_output(rule.output, ranges)?
+        } else {
+            self.compile_rules(id, rule_idx + 1, ranges)?
+        };
+
+        ranges[field as usize] = (threshold, hi);
+        let rest = if pass_is_less {
+            self.compile_rules(id, rule_idx + 1, ranges)?
+        } else {
+            self.compile_output(rule.output, ranges)?
+        };
+
+        // Leaves are shared, so equal indices mean the comparison can't change the outcome.
+        if less == rest {
+            return Ok(less);
+        }
+
+        self.nodes.push(Decision::Split {
+            field,
+            threshold,
+            less,
+            rest,
+        });
+        Ok(self.nodes.len() - 1)
+    }
+}
+
+fn compile(workflows: &WorkFlows, start: WorkFlowId) -> Result<DecisionTree> {
+    let mut compiler = Compiler {
+        workflows,
+        nodes: vec![Decision::Accept, Decision::Reject],
+        in_progress: vec![false; workflows.flows.len()],
+    };
+
+    let root = compiler.compile_output(RuleOutput::Workflow(start), [(0, u32::MAX); 4])?;
+
+    Ok(DecisionTree {
+        nodes: compiler.nodes,
+        root,
+    })
+}
+
+#[derive(Debug)]
+pub struct System {
+    tree: DecisionTree,
+    parts: Vec<Part>,
+}
+
+impl System {
+    /// The parts' ratings, in `xmas` order.
+    pub fn parts(&self) -> impl Iterator<Item = [u32; 4]> + '_ {
+        self.parts.iter().map(|p| p.0)
+    }
+
+    /// Whether the workflows accept a part with the given `xmas` ratings.
+    pub fn accepts(&self, ratings: [u32; 4]) -> bool {
+        self.tree.accepts(&Part(ratings))
+    }
+}
 
+/// Parses the workflows section of the input, returning them with the ID of `in`.
+pub fn parse_workflows(workflows_str: &str) -> (WorkFlows<'_>, WorkFlowId) {
     let mut idgen = IdGen::<WorkFlow, _>::new();
     for wf in workflows_str.lines().map(str::trim) {
         let (name, rules) = wf.split_once('{').unwrap();
         let rules = rules.strip_suffix('}').unwrap();
 
         let id = idgen.id_of(name);
+        idgen[id].name = name;
+        idgen[id].defined = true;
         for rule in rules.split(',').map(str::trim) {
             let rule = match rule.split_once(':') {
                 Some((condition, output)) => {
                     let output = match output {
                         "R" => RuleOutput::Reject,
                         "A" => RuleOutput::Accept,
-                        _ => RuleOutput::Workflow(idgen.id_of(output)),
+                        _ => {
+                            let out_id = idgen.id_of(output);
+                            idgen[out_id].name = output;
+                            RuleOutput::Workflow(out_id)
+                        }
                     };
 
                     let int = condition[2..].parse().unwrap();
@@ -136,7 +351,11 @@ fn parse(input: &str) -> Result<(WorkFlows, Vec<Part>, WorkFlowId)> {
                     let output = match rule {
                         "R" => RuleOutput::Reject,
                         "A" => RuleOutput::Accept,
-                        _ => RuleOutput::Workflow(idgen.id_of(rule)),
+                        _ => {
+                            let out_id = idgen.id_of(rule);
+                            idgen[out_id].name = rule;
+                            RuleOutput::Workflow(out_id)
+                        }
                     };
                     Rule {
                         cond: RuleCondition::None,
@@ -149,6 +368,20 @@ fn parse(input: &str) -> Result<(WorkFlows, Vec<Part>, WorkFlowId)> {
         }
     }
 
+    let start = idgen.id_of("in");
+    idgen[start].name = "in";
+    let workflows = WorkFlows {
+        flows: idgen.into_items(),
+    };
+
+    (workflows, start)
+}
+
+fn parse(input: &str) -> Result<System> {
+    let (workflows_str, parts_str) = input.split_once("\n\n").unwrap();
+    let (workflows, start) = parse_workflows(workflows_str);
+    let tree = compile(&workflows, start)?;
+
     let mut parts = Vec::new();
     for pt in parts_str.lines().map(str::trim) {
         let pt = &pt[1..pt.len() - 1];
@@ -168,49 +401,217 @@ fn parse(input: &str) -> Result<(WorkFlows, Vec<Part>, WorkFlowId)> {
         parts.push(part);
     }
 
-    let in_id = idgen.id_of("in");
+    Ok(System { tree, parts })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic<'a> {
+    MissingStart,
+    UndefinedWorkflow { name: &'a str, used_by: &'a str },
+    DeadRule { workflow: &'a str, rule: usize },
+    Unreachable { workflow: &'a str },
+    Cycle { workflows: Vec<&'a str> },
+}
 
-    Ok((
-        WorkFlows {
-            flows: idgen.into_items(),
-        },
-        parts,
-        in_id,
-    ))
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::MissingStart => f.write_str("there is no `in` workflow to start from"),
+            Diagnostic::UndefinedWorkflow { name, used_by } => {
+                write!(f, "`{used_by}` sends parts to undefined workflow `{name}`")
+            }
+            Diagnostic::DeadRule { workflow, rule } => {
+                write!(f, "rule {} of `{workflow}` can never fire", rule + 1)
+            }
+            Diagnostic::Unreachable { workflow } => {
+                write!(f, "`{workflow}` is unreachable from `in`")
+            }
+            Diagnostic::Cycle { workflows } => {
+                write!(f, "parts can loop forever: {}", workflows.join(" -> "))
+            }
+        }
+    }
 }
 
-fn part1((workflows, parts, start): &(WorkFlows, Vec<Part>, WorkFlowId)) -> u32 {
-    let mut sum = 0;
-    for part in parts {
-        let mut cur_wf_id = *start;
+/// Finds the rules in a workflow that can fire, given that parts only reach a rule after
+/// failing every earlier condition.
+fn live_rules(flow: &WorkFlow) -> Vec<bool> {
+    let mut ranges: FieldRanges = [(0, u32::MAX); 4];
+    let mut exhausted = false;
+
+    flow.rules
+        .iter()
+        .map(|rule| {
+            if exhausted {
+                return false;
+            }
 
-        'cond_check: loop {
-            let cur_wf = &workflows[cur_wf_id];
-            for rule in &cur_wf.rules {
-                match rule.cond {
-                    RuleCondition::Less(f, i) if part[f] >= i as u32 => {
-                        continue;
-                    }
-                    RuleCondition::Greater(f, i) if part[f] <= i as u32 => continue,
-                    _ => {}
+            match rule.cond {
+                RuleCondition::None => {
+                    exhausted = true;
+                    true
                 }
+                RuleCondition::Less(f, i) => {
+                    let (lo, hi) = &mut ranges[f as usize];
+                    let live = *lo < i as u32;
+                    *lo = (*lo).max(i as u32);
+                    exhausted = *lo > *hi;
+                    live
+                }
+                RuleCondition::Greater(f, i) => {
+                    let (lo, hi) = &mut ranges[f as usize];
+                    let live = *hi > i as u32;
+                    *hi = (*hi).min(i as u32);
+                    exhausted = *lo > *hi;
+                    live
+                }
+            }
+        })
+        .collect()
+}
 
-                match rule.output {
-                    RuleOutput::Accept => {
-                        sum += part.0.into_iter().sum::<u32>();
-                        break 'cond_check;
-                    }
-                    RuleOutput::Reject => break 'cond_check,
-                    RuleOutput::Workflow(next) => {
-                        cur_wf_id = next;
-                        continue 'cond_check;
-                    }
+/// Follows the workflows from `in` like `Compiler` does, so only paths some part can take
+/// count towards reachability and cycles.
+struct Walker<'w, 'a> {
+    workflows: &'w WorkFlows<'a>,
+    visited: Vec<bool>,
+    path: Vec<usize>,
+    cycles: Vec<Vec<&'a str>>,
+}
+
+impl Walker<'_, '_> {
+    fn walk_output(&mut self, output: RuleOutput, ranges: FieldRanges) {
+        let RuleOutput::Workflow(id) = output else {
+            return;
+        };
+
+        let flow = &self.workflows[id];
+        if !flow.defined {
+            return;
+        }
+
+        if let Some(cycle_start) = self.path.iter().position(|&p| p == id.0) {
+            let mut cycle: Vec<_> = self.path[cycle_start..]
+                .iter()
+                .map(|&p| self.workflows.flows[p].name)
+                .collect();
+            cycle.push(flow.name);
+            // Different ranges can take the same loop.
+            if !self.cycles.contains(&cycle) {
+                self.cycles.push(cycle);
+            }
+            return;
+        }
+
+        self.visited[id.0] = true;
+        self.path.push(id.0);
+        self.walk_rules(id, ranges);
+        self.path.pop();
+    }
+
+    fn walk_rules(&mut self, id: WorkFlowId, mut ranges: FieldRanges) {
+        let workflows = self.workflows;
+
+        for &rule in &workflows[id].rules {
+            let (field, threshold, pass_is_less) = match rule.cond {
+                RuleCondition::None => return self.walk_output(rule.output, ranges),
+                RuleCondition::Less(f, i) => (f as usize, i as u32, true),
+                RuleCondition::Greater(f, i) => (f as usize, i as u32 + 1, false),
+            };
+
+            let (lo, hi) = ranges[field];
+            let less = (lo < threshold).then(|| (lo, hi.min(threshold - 1)));
+            let rest = (hi >= threshold).then(|| (lo.max(threshold), hi));
+            let (pass, fail) = if pass_is_less {
+                (less, rest)
+            } else {
+                (rest, less)
+            };
+
+            if let Some(pass) = pass {
+                let mut pass_ranges = ranges;
+                pass_ranges[field] = pass;
+                self.walk_output(rule.output, pass_ranges);
+            }
+
+            let Some(fail) = fail else {
+                return;
+            };
+            ranges[field] = fail;
+        }
+    }
+}
+
+/// Reports workflows that can't be reached or never finish, and rules that can never fire.
+pub fn validate<'a>(workflows: &WorkFlows<'a>, start: WorkFlowId) -> Vec<Diagnostic<'a>> {
+    let mut diagnostics = Vec::new();
+    let live: Vec<_> = workflows.flows.iter().map(live_rules).collect();
+
+    for flow in workflows.flows.iter().filter(|f| f.defined) {
+        for rule in &flow.rules {
+            if let RuleOutput::Workflow(next) = rule.output {
+                if !workflows[next].defined {
+                    diagnostics.push(Diagnostic::UndefinedWorkflow {
+                        name: workflows[next].name,
+                        used_by: flow.name,
+                    });
                 }
             }
         }
     }
 
-    sum
+    for (flow, live) in workflows.flows.iter().zip(&live) {
+        for (rule, _) in live.iter().enumerate().filter(|(_, &l)| !l) {
+            diagnostics.push(Diagnostic::DeadRule {
+                workflow: flow.name,
+                rule,
+            });
+        }
+    }
+
+    // Without a start, every workflow would also be reported as unreachable.
+    if !workflows[start].defined {
+        diagnostics.push(Diagnostic::MissingStart);
+        return diagnostics;
+    }
+
+    // Each cycle is a loop that some part would follow forever.
+    let mut walker = Walker {
+        workflows,
+        visited: vec![false; workflows.flows.len()],
+        path: Vec::new(),
+        cycles: Vec::new(),
+    };
+    walker.walk_output(RuleOutput::Workflow(start), [(0, u32::MAX); 4]);
+
+    diagnostics.extend(
+        walker
+            .cycles
+            .into_iter()
+            .map(|workflows| Diagnostic::Cycle { workflows }),
+    );
+
+    for (flow, _) in workflows
+        .flows
+        .iter()
+        .zip(walker.visited)
+        .filter(|(f, v)| f.defined && !v)
+    {
+        diagnostics.push(Diagnostic::Unreachable {
+            workflow: flow.name,
+        });
+    }
+
+    diagnostics
+}
+
+fn part1(system: &System) -> u64 {
+    system
+        .parts
+        .iter()
+        .filter(|part| system.tree.accepts(part))
+        .map(|part| part.0.into_iter().map(u64::from).sum::<u64>())
+        .sum()
 }
 
 #[cfg(test)]
@@ -231,4 +632,77 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn large_ratings_test() {
+        let input = "in{x<10:R,A}\n\n{x=4000000000,m=4000000000,a=1,s=2}";
+        let parsed = parse(input).unwrap();
+        assert_eq!(part1(&parsed), 8_000_000_003);
+    }
+
+    #[test]
+    fn compile_errors_test() {
+        let tests = [
+            ("in{x<10:A,foo}", "never defined"),
+            ("in{x<10:A,foo}\nfoo{m>5:in,R}", "loop"),
+            ("in{x<10:A,s>5:R}", "fallback"),
+        ];
+
+        for (workflows, expected) in tests {
+            let input = format!("{workflows}\n\n{{x=1,m=2,a=3,s=4}}");
+            let err = parse(&input).unwrap_err().to_string();
+            assert!(err.contains(expected), "{workflows}: {err}");
+        }
+
+        // The loop through `in` is unreachable, as x can't be both below 10 and above 20.
+        let input = "in{x<10:foo,A}\nfoo{x>20:in,R}\n\n{x=1,m=2,a=3,s=4}";
+        let parsed = parse(input).unwrap();
+        assert_eq!(part1(&parsed), 0);
+    }
+
+    #[test]
+    fn validate_test() {
+        let workflows = "in{x<10:a,x<5:R,m>3:b,A}
+            a{s>100:c,gone}
+            b{a<50:b,R}
+            c{x>5:in,A}
+            lost{A}";
+
+        let (workflows, start) = parse_workflows(workflows);
+        let expected = [
+            Diagnostic::UndefinedWorkflow {
+                name: "gone",
+                used_by: "a",
+            },
+            Diagnostic::DeadRule {
+                workflow: "in",
+                rule: 1,
+            },
+            Diagnostic::Cycle {
+                workflows: vec!["in", "a", "c", "in"],
+            },
+            Diagnostic::Cycle {
+                workflows: vec!["b", "b"],
+            },
+            Diagnostic::Unreachable { workflow: "lost" },
+        ];
+        let actual = validate(&workflows, start);
+
+        assert_eq!(expected.as_slice(), actual);
+        assert_eq!(actual[1].to_string(), "rule 2 of `in` can never fire");
+
+        let (workflows, start) = parse_workflows("a{x<10:in,A}\nb{R}");
+        let expected = [
+            Diagnostic::UndefinedWorkflow {
+                name: "in",
+                used_by: "a",
+            },
+            Diagnostic::MissingStart,
+        ];
+        assert_eq!(expected.as_slice(), validate(&workflows, start));
+
+        // `foo` only sends parts back to `in` when x is above 20, which `in` never passes on.
+        let (workflows, start) = parse_workflows("in{x<10:foo,A}\nfoo{x>20:in,R}");
+        assert_eq!(validate(&workflows, start), []);
+    }
 }
@@ -1,38 +1,43 @@
 use std::{
     collections::{HashMap, VecDeque},
-    ops::{Index, Not},
+    fmt::Display,
+    ops::Index,
 };
 
 use aoc_lib::{
     misc::{IdGen, IdType},
-    Bench, BenchResult, Day, NoError, ParseResult, UserError,
+    Day,
 };
-use color_eyre::{Report, Result};
+use color_eyre::{eyre::bail, Result};
 use smallvec::SmallVec;
 
-pub const DAY: Day = Day {
-    day: 20,
-    name: "Pulse Propagation",
-    part_1: run_part1,
-    part_2: None,
-    // part_2: Some(run_part2),
-    other: &[("Parse", run_parse)],
-};
+use crate::solution::{self, run_parse, Answer, Solution};
 
-fn run_part1(input: &str, b: Bench) -> BenchResult {
-    let data = parse(input).map_err(UserError)?;
-    b.bench(|| Ok::<_, NoError>(part1(&data)))
-}
+pub struct Day20;
 
-fn run_parse(input: &str, b: Bench) -> BenchResult {
-    b.bench(|| {
-        let data = parse(input).map_err(UserError)?;
-        Ok::<_, Report>(ParseResult(data))
-    })
+impl Solution for Day20 {
+    const DAY: u8 = 20;
+    const NAME: &'static str = "Pulse Propagation";
+
+    type Parsed<'a> = ModuleSystem;
+    type Part1 = u128;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>> {
+        parse(input)
+    }
+
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1> {
+        Ok(part1(data)?)
+    }
 }
 
+pub const DAY: Day = solution::part1_only::<Day20>(&[("Parse", run_parse::<Day20>)]);
+
+pub const ANSWERS: &[Answer] = &[Answer::part1::<Day20>()];
+
+/// A module's index in the order modules first appear in the input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct ModuleId(u8);
+pub struct ModuleId(u8);
 impl IdType for ModuleId {
     fn from_usize(i: usize) -> Self {
         Self(i as u8)
@@ -51,10 +56,22 @@ enum ModuleType {
 }
 
 #[derive(Debug, Clone)]
-struct Module {
+pub struct Module {
     kind: ModuleType,
     inputs: SmallVec<[ModuleId; 8]>,
     outputs: SmallVec<[ModuleId; 8]>,
+    // Our index in each output's `inputs`, which is its bit in the output's memory.
+    output_slots: SmallVec<[u8; 8]>,
+}
+
+impl Module {
+    pub fn inputs(&self) -> &[ModuleId] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[ModuleId] {
+        &self.outputs
+    }
 }
 
 impl Default for Module {
@@ -63,16 +80,31 @@ impl Default for Module {
             kind: ModuleType::Broadcast,
             inputs: Default::default(),
             outputs: Default::default(),
+            output_slots: Default::default(),
         }
     }
 }
 
 #[derive(Debug)]
-struct ModuleSystem {
+pub struct ModuleSystem {
     modules: Vec<Module>,
     start: ModuleId,
 }
 
+impl ModuleSystem {
+    pub fn modules(&self) -> impl Iterator<Item = (ModuleId, &Module)> {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (ModuleId::from_usize(i), m))
+    }
+
+    /// The broadcaster, which receives the button's pulse.
+    pub fn start(&self) -> ModuleId {
+        self.start
+    }
+}
+
 impl Index<ModuleId> for ModuleSystem {
     type Output = Module;
 
@@ -97,8 +129,14 @@ fn parse(input: &str) -> Result<ModuleSystem> {
         modules[id].kind = kind;
         for output in outputs.split(',').map(str::trim) {
             let output_id = modules.id_of(output);
+            let slot = modules[output_id].inputs.len();
+            if slot >= 64 {
+                bail!("module `{output}` has more than 64 inputs");
+            }
+
             modules[output_id].inputs.push(id);
             modules[id].outputs.push(output_id);
+            modules[id].output_slots.push(slot as u8);
         }
     }
 
@@ -109,101 +147,222 @@ fn parse(input: &str) -> Result<ModuleSystem> {
     })
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Pulse {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pulse {
     High,
     Low,
 }
 
-impl Not for Pulse {
-    type Output = Self;
+/// Runs button presses through a module system, owning all the module state.
+#[derive(Debug)]
+pub struct Simulator<'a> {
+    system: &'a ModuleSystem,
+    /// One bit per module, set when a flip-flop is on.
+    flip_flops: Vec<u64>,
+    /// Per-module bitmask of which input slots last sent a high pulse.
+    memories: Vec<u64>,
+    /// Pending pulses as `(src, pulse, dst, src's input slot on dst)`.
+    queue: VecDeque<(ModuleId, Pulse, ModuleId, u8)>,
+}
 
-    fn not(self) -> Self::Output {
-        match self {
-            Pulse::High => Pulse::Low,
-            Pulse::Low => Pulse::High,
-        }
+impl<'a> Simulator<'a> {
+    pub fn new(system: &'a ModuleSystem) -> Self {
+        let mut sim = Self {
+            system,
+            flip_flops: Vec::new(),
+            memories: Vec::new(),
+            queue: VecDeque::new(),
+        };
+        sim.reset();
+        sim
+    }
+
+    pub fn reset(&mut self) {
+        let num_modules = self.system.modules.len();
+        self.flip_flops.clear();
+        self.flip_flops.resize(num_modules.div_ceil(64), 0);
+        self.memories.clear();
+        self.memories.resize(num_modules, 0);
+        self.queue.clear();
+    }
+
+    /// Whether every module is back in the state it started in.
+    pub fn is_initial(&self) -> bool {
+        self.flip_flops
+            .iter()
+            .chain(&self.memories)
+            .all(|&w| w == 0)
+    }
+
+    /// Every flip-flop state followed by every conjunction memory.
+    fn snapshot(&self) -> Vec<u64> {
+        self.flip_flops
+            .iter()
+            .chain(&self.memories)
+            .copied()
+            .collect()
+    }
+
+    /// Presses the button, returning every pulse sent as `(src, pulse, dst)` in the order
+    /// they're processed. The press is completed even if the events aren't all consumed.
+    pub fn press(&mut self) -> Press<'_, 'a> {
+        let start = self.system.start;
+        self.queue.push_back((start, Pulse::Low, start, 0));
+        Press { sim: self }
+    }
+
+    fn next_pulse(&mut self, pulse: Pulse, dst: ModuleId, slot: u8) -> Option<Pulse> {
+        let module = &self.system[dst];
+        let new_pulse = match (module.kind, pulse) {
+            (ModuleType::FlipFlop, Pulse::High) => return None,
+            (ModuleType::FlipFlop, Pulse::Low) => {
+                let idx = dst.0 as usize;
+                let word = &mut self.flip_flops[idx / 64];
+                *word ^= 1 << (idx % 64);
+
+                if *word & (1 << (idx % 64)) != 0 {
+                    Pulse::High
+                } else {
+                    Pulse::Low
+                }
+            }
+            (ModuleType::Conjunction, _) => {
+                let memory = &mut self.memories[dst.0 as usize];
+                match pulse {
+                    Pulse::High => *memory |= 1 << slot,
+                    Pulse::Low => *memory &= !(1 << slot),
+                }
+
+                let all_inputs = u64::MAX >> (64 - module.inputs.len());
+                if *memory == all_inputs {
+                    Pulse::Low
+                } else {
+                    Pulse::High
+                }
+            }
+            (ModuleType::Broadcast, _) => pulse,
+        };
+        Some(new_pulse)
     }
 }
 
-#[derive(Debug, Default)]
-struct PulseQueue {
-    queue: VecDeque<(ModuleId, Pulse, ModuleId)>,
-    num_high: u32,
-    num_low: u32,
+pub struct Press<'p, 'a> {
+    sim: &'p mut Simulator<'a>,
 }
 
-impl PulseQueue {
-    fn next(&mut self) -> Option<(ModuleId, Pulse, ModuleId)> {
-        self.queue.pop_front()
-    }
+impl Iterator for Press<'_, '_> {
+    type Item = (ModuleId, Pulse, ModuleId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (src, pulse, dst, slot) = self.sim.queue.pop_front()?;
 
-    fn add(&mut self, src: ModuleId, pulse: Pulse, dst: ModuleId) {
-        match pulse {
-            Pulse::High => self.num_high += 1,
-            Pulse::Low => self.num_low += 1,
+        if let Some(new_pulse) = self.sim.next_pulse(pulse, dst, slot) {
+            let module = &self.sim.system[dst];
+            for (&output, &out_slot) in module.outputs.iter().zip(&module.output_slots) {
+                self.sim.queue.push_back((dst, new_pulse, output, out_slot));
+            }
         }
 
-        self.queue.push_back((src, pulse, dst))
+        Some((src, pulse, dst))
+    }
+}
+
+impl Drop for Press<'_, '_> {
+    fn drop(&mut self) {
+        self.for_each(drop);
     }
 }
 
-fn next_pulse(
-    module: &Module,
-    pulse: Pulse,
-    flip_flop_state: &mut [Pulse],
-    dst: ModuleId,
-    inputs: &mut [HashMap<ModuleId, Pulse>],
-    src: ModuleId,
-) -> Option<Pulse> {
-    let new_pulse = match (module.kind, pulse) {
-        (ModuleType::FlipFlop, Pulse::High) => return None,
-        (ModuleType::FlipFlop, Pulse::Low) => {
-            let state = &mut flip_flop_state[dst.0 as usize];
-            *state = !*state;
-            *state
+/// Presses to simulate while looking for a repeated state. Memory grows with every press until
+/// a repeat is found, and real inputs take far longer than this to repeat.
+pub const MAX_SIMULATED_PRESSES: u64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PulseError {
+    NoRepeat,
+    Overflow,
+}
+
+impl Display for PulseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PulseError::NoRepeat => write!(
+                f,
+                "network doesn't repeat within {MAX_SIMULATED_PRESSES} presses to extrapolate from"
+            ),
+            PulseError::Overflow => f.write_str("pulse count doesn't fit in a u64"),
         }
-        (ModuleType::Conjunction, _) => {
-            let inputs = &mut inputs[dst.0 as usize];
-            inputs.insert(src, pulse);
-
-            if inputs.values().all(|&v| v == Pulse::High) {
-                Pulse::Low
-            } else {
-                Pulse::High
+    }
+}
+
+impl std::error::Error for PulseError {}
+
+/// Counts the (high, low) pulses sent over the given number of presses.
+///
+/// Once the network returns to an earlier state, the presses in between repeat forever, so the
+/// rest of the count can be extrapolated from them.
+pub fn pulse_totals(data: &ModuleSystem, presses: u64) -> Result<(u64, u64), PulseError> {
+    let mut sim = Simulator::new(data);
+    let mut seen = HashMap::new();
+    // Running (high, low) totals after each press.
+    let mut totals = vec![(0, 0)];
+
+    for press in 1..=presses.min(MAX_SIMULATED_PRESSES) as usize {
+        let (mut num_high, mut num_low) = totals[press - 1];
+        for (_, pulse, _) in sim.press() {
+            match pulse {
+                Pulse::High => num_high += 1,
+                Pulse::Low => num_low += 1,
             }
         }
-        (ModuleType::Broadcast, _) => pulse,
-    };
-    Some(new_pulse)
-}
-
-fn part1(data: &ModuleSystem) -> u32 {
-    let mut pulse_queue = PulseQueue::default();
-    let mut flip_flop_state = vec![Pulse::Low; data.modules.len()];
-    let mut inputs: Vec<HashMap<_, _>> = data
-        .modules
-        .iter()
-        .map(|m| m.inputs.iter().map(|&m| (m, Pulse::Low)).collect())
-        .collect();
-
-    for _ in 0..1000 {
-        pulse_queue.add(data.start, Pulse::Low, data.start);
-        while let Some((src, pulse, dst)) = pulse_queue.next() {
-            let module = &data[dst];
-            let Some(new_pulse) =
-                next_pulse(module, pulse, &mut flip_flop_state, dst, &mut inputs, src)
-            else {
-                continue;
-            };
+        totals.push((num_high, num_low));
+
+        // The initial state is the common repeat, and is cheap to check.
+        let cycle_start = if sim.is_initial() {
+            Some(0)
+        } else {
+            let snapshot = sim.snapshot();
+            let prev = seen.get(&snapshot).copied();
+            seen.insert(snapshot, press);
+            prev
+        };
 
-            for &output in &module.outputs {
-                pulse_queue.add(dst, new_pulse, output);
-            }
+        if let Some(cycle_start) = cycle_start {
+            let cycle_len = (press - cycle_start) as u64;
+            let (start_high, start_low) = totals[cycle_start];
+            let (cycle_high, cycle_low) = (num_high - start_high, num_low - start_low);
+
+            let remaining = presses - cycle_start as u64;
+            let repeats = remaining / cycle_len;
+            let (rem_high, rem_low) = totals[cycle_start + (remaining % cycle_len) as usize];
+
+            let extrapolate = |rem: u64, cycle: u64| {
+                cycle
+                    .checked_mul(repeats)
+                    .and_then(|total| total.checked_add(rem))
+                    .ok_or(PulseError::Overflow)
+            };
+            return Ok((
+                extrapolate(rem_high, cycle_high)?,
+                extrapolate(rem_low, cycle_low)?,
+            ));
         }
     }
 
-    pulse_queue.num_high * pulse_queue.num_low
+    totals
+        .get(presses as usize)
+        .copied()
+        .ok_or(PulseError::NoRepeat)
+}
+
+/// The number of high pulses times the number of low pulses over the given number of presses.
+pub fn pulse_product(data: &ModuleSystem, presses: u64) -> Result<u128, PulseError> {
+    let (num_high, num_low) = pulse_totals(data, presses)?;
+    Ok(num_high as u128 * num_low as u128)
+}
+
+fn part1(data: &ModuleSystem) -> Result<u128, PulseError> {
+    pulse_product(data, 1000)
 }
 
 #[cfg(test)]
@@ -222,10 +381,112 @@ mod tests {
             let (example, expected) = data.split_once("---").unwrap();
 
             let parsed = parse(example.trim()).unwrap();
-            let expected: u32 = expected.trim().parse().unwrap();
-            let actual = part1(&parsed);
+            let expected: u128 = expected.trim().parse().unwrap();
+            let actual = part1(&parsed).unwrap();
 
             assert_eq!(expected, actual);
         }
     }
+
+    #[test]
+    fn pulse_cycle_test() {
+        // Example 1 repeats every press, example 2 every four.
+        let tests = [(1, 1, 4, 8), (2, 4, 11, 17)];
+        for (id, cycle_len, cycle_high, cycle_low) in tests {
+            let data = aoc_lib::input(DAY.day)
+                .example(Example::Part1, id)
+                .open()
+                .unwrap();
+
+            let (example, _) = data.split_once("---").unwrap();
+            let parsed = parse(example.trim()).unwrap();
+
+            let presses = 1_000_000_000_000;
+            let repeats = presses / cycle_len;
+            let expected = Ok((cycle_high * repeats, cycle_low * repeats));
+            let actual = pulse_totals(&parsed, presses);
+            assert_eq!(expected, actual, "{id}");
+
+            // Part way through a cycle should match pressing the button that many times.
+            let mut sim = Simulator::new(&parsed);
+            let mut expected = (0, 0);
+            for _ in 0..1003 {
+                for (_, pulse, _) in sim.press() {
+                    match pulse {
+                        Pulse::High => expected.0 += 1,
+                        Pulse::Low => expected.1 += 1,
+                    }
+                }
+            }
+            let actual = pulse_totals(&parsed, 1003);
+            assert_eq!(Ok(expected), actual, "{id}");
+
+            assert_eq!(
+                pulse_totals(&parsed, u64::MAX),
+                Err(PulseError::Overflow),
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn pulse_no_repeat_test() {
+        // A chain of flip-flops counts presses in binary, so it only repeats every 2^20 presses.
+        let mut input = String::from("broadcaster -> f0\n");
+        for i in 0..20 {
+            input.push_str(&format!("%f{i} -> f{}\n", i + 1));
+        }
+        let parsed = parse(&input).unwrap();
+
+        let (high, low) = pulse_totals(&parsed, MAX_SIMULATED_PRESSES).unwrap();
+        assert_eq!(high + low, 4 * MAX_SIMULATED_PRESSES - 1);
+        assert_eq!(
+            pulse_totals(&parsed, 1_000_000_000_000),
+            Err(PulseError::NoRepeat)
+        );
+    }
+
+    #[test]
+    fn simulator_test() {
+        let data = aoc_lib::input(DAY.day)
+            .example(Example::Part1, 2)
+            .open()
+            .unwrap();
+
+        let (example, _) = data.split_once("---").unwrap();
+        let parsed = parse(example.trim()).unwrap();
+        let output = parsed
+            .modules
+            .iter()
+            .position(|m| m.outputs.is_empty())
+            .map(ModuleId::from_usize)
+            .unwrap();
+        let mut sim = Simulator::new(&parsed);
+
+        // The network returns to its initial state every fourth press.
+        let mut output_pulses = Vec::new();
+        for press in 1..=4 {
+            output_pulses.extend(
+                sim.press()
+                    .filter(|&(_, _, dst)| dst == output)
+                    .map(|(_, pulse, _)| pulse),
+            );
+            assert_eq!(sim.is_initial(), press == 4, "{press}");
+        }
+
+        assert_eq!(output_pulses.len(), 6);
+        assert_eq!(
+            output_pulses.iter().filter(|&&p| p == Pulse::Low).count(),
+            2
+        );
+
+        // Abandoning a press part way through still finishes it.
+        sim.reset();
+        sim.press().next();
+        sim.press().next();
+        sim.press().next();
+        assert!(!sim.is_initial());
+        sim.press().next();
+        assert!(sim.is_initial());
+    }
 }
@@ -0,0 +1,23 @@
+//! Solutions for Advent of Code 2023.
+//!
+//! Each day lives in [`days`] as a unit struct, such as [`days::day05::Day05`], implementing
+//! [`Solution`](solution::Solution) and, if the second part is done, [`Part2`](solution::Part2),
+//! or [`SolvePart2`](solution::SolvePart2) when that part parses the input differently.
+//! Parsing gives a puzzle type that the parts then solve from:
+//!
+//! ```
+//! use advent_of_code_2023::{days::day01::Day01, solution::Solution};
+//!
+//! # fn main() -> color_eyre::Result<()> {
+//! let parsed = Day01::parse("1abc2\npqr3stu8vwx")?;
+//! assert_eq!(Day01::part1(&parsed)?, 12 + 38);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each day also exposes the pieces its parts are built from, such as
+//! [`days::day19::validate`] or [`days::day20::Simulator`], for tools that need more than the
+//! answers.
+
+pub mod days;
+pub mod solution;
@@ -0,0 +1,131 @@
+use std::fmt::Display;
+
+use aoc_lib::{Bench, BenchResult, Day, ParseResult, UserError};
+use color_eyre::{Report, Result};
+
+type Runner = fn(&str, Bench) -> BenchResult;
+
+/// A day's puzzle, split into parsing the input and solving the first part.
+pub trait Solution {
+    const DAY: u8;
+    const NAME: &'static str;
+
+    type Parsed<'a>;
+    type Part1: Display;
+
+    fn parse(input: &str) -> Result<Self::Parsed<'_>>;
+    fn part1(data: &Self::Parsed<'_>) -> Result<Self::Part1>;
+}
+
+/// The second part of a puzzle, solved from the same parsed input as the first.
+pub trait Part2: Solution {
+    type Part2: Display;
+
+    fn part2(data: &Self::Parsed<'_>) -> Result<Self::Part2>;
+}
+
+/// The second part of a puzzle, parsed its own way. Every [`Part2`] gets this for free, so only
+/// days whose parts read the input differently implement it directly.
+pub trait SolvePart2: Solution {
+    type Parsed2<'a>;
+    type Part2: Display;
+
+    fn parse_part2(input: &str) -> Result<Self::Parsed2<'_>>;
+    fn part2(data: &Self::Parsed2<'_>) -> Result<Self::Part2>;
+}
+
+impl<S: Part2> SolvePart2 for S {
+    type Parsed2<'a> = S::Parsed<'a>;
+    type Part2 = <S as Part2>::Part2;
+
+    fn parse_part2(input: &str) -> Result<Self::Parsed2<'_>> {
+        S::parse(input)
+    }
+
+    fn part2(data: &Self::Parsed2<'_>) -> Result<Self::Part2> {
+        <S as Part2>::part2(data)
+    }
+}
+
+pub fn run_part1<S: Solution>(input: &str, b: Bench) -> BenchResult {
+    let data = S::parse(input).map_err(UserError)?;
+    b.bench(|| S::part1(&data))
+}
+
+pub fn run_part2<S: SolvePart2>(input: &str, b: Bench) -> BenchResult {
+    let data = S::parse_part2(input).map_err(UserError)?;
+    b.bench(|| S::part2(&data))
+}
+
+pub fn run_parse<S: Solution>(input: &str, b: Bench) -> BenchResult {
+    b.bench(|| {
+        let data = S::parse(input).map_err(UserError)?;
+        Ok::<_, Report>(ParseResult(data))
+    })
+}
+
+pub fn run_parse_part2<S: SolvePart2>(input: &str, b: Bench) -> BenchResult {
+    b.bench(|| {
+        let data = S::parse_part2(input).map_err(UserError)?;
+        Ok::<_, Report>(ParseResult(data))
+    })
+}
+
+/// Computes one part's answer straight from the input, without going through the bench harness.
+/// Used to check every implementation of a part against a known answer.
+#[derive(Clone, Copy)]
+pub struct Answer {
+    pub name: &'static str,
+    pub part: u8,
+    pub run: fn(&str) -> Result<String>,
+}
+
+impl Answer {
+    pub const fn part1<S: Solution>() -> Self {
+        Self {
+            name: "Part 1",
+            part: 1,
+            run: answer_part1::<S>,
+        }
+    }
+
+    pub const fn part2<S: SolvePart2>() -> Self {
+        Self {
+            name: "Part 2",
+            part: 2,
+            run: answer_part2::<S>,
+        }
+    }
+}
+
+fn answer_part1<S: Solution>(input: &str) -> Result<String> {
+    let data = S::parse(input)?;
+    Ok(S::part1(&data)?.to_string())
+}
+
+fn answer_part2<S: SolvePart2>(input: &str) -> Result<String> {
+    let data = S::parse_part2(input)?;
+    Ok(S::part2(&data)?.to_string())
+}
+
+/// The runner entry for a day with both parts solved.
+pub const fn day<S: SolvePart2>(other: &'static [(&'static str, Runner)]) -> Day {
+    Day {
+        day: S::DAY,
+        name: S::NAME,
+        part_1: run_part1::<S>,
+        part_2: Some(run_part2::<S>),
+        other,
+    }
+}
+
+/// The runner entry for a day with only the first part solved.
+pub const fn part1_only<S: Solution>(other: &'static [(&'static str, Runner)]) -> Day {
+    Day {
+        day: S::DAY,
+        name: S::NAME,
+        part_1: run_part1::<S>,
+        part_2: None,
+        other,
+    }
+}
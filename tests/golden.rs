@@ -0,0 +1,72 @@
+//! Checks every implementation of every part against known answers for the real inputs.
+//!
+//! The answers are read from `answers.txt` in the crate root, or the file named by the
+//! `AOC_ANSWERS` environment variable. Each line is `<day> <part> <answer>`, and blank lines
+//! or lines starting with `#` are ignored. Days without an input or an answer are skipped.
+
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use advent_of_code_2023::days::ANSWERS;
+
+fn answers_path() -> PathBuf {
+    match env::var_os("AOC_ANSWERS") {
+        Some(path) => path.into(),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("answers.txt"),
+    }
+}
+
+fn parse_answers(contents: &str) -> HashMap<(u8, u8), String> {
+    let mut answers = HashMap::new();
+    for (idx, line) in contents.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<_> = line.split_ascii_whitespace().collect();
+        let [day, part, answer] = fields[..] else {
+            panic!("answers line {}: expected `<day> <part> <answer>`", idx + 1);
+        };
+        let day = day.parse().expect("invalid day");
+        let part = part.parse().expect("invalid part");
+        answers.insert((day, part), answer.to_owned());
+    }
+    answers
+}
+
+#[test]
+fn golden_answers() {
+    let path = answers_path();
+    let Ok(contents) = fs::read_to_string(&path) else {
+        eprintln!("no answers at {}, skipping", path.display());
+        return;
+    };
+    let expected = parse_answers(&contents);
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for &(day, implementations) in ANSWERS {
+        let Ok(input) = aoc_lib::input(day).open() else {
+            eprintln!("day {day}: no input, skipping");
+            continue;
+        };
+
+        for answer in implementations {
+            let Some(expected) = expected.get(&(day, answer.part)) else {
+                continue;
+            };
+
+            checked += 1;
+            match (answer.run)(&input) {
+                Ok(actual) if actual == *expected => {}
+                Ok(actual) => failures.push(format!(
+                    "day {day} {}: expected {expected}, got {actual}",
+                    answer.name
+                )),
+                Err(e) => failures.push(format!("day {day} {}: {e}", answer.name)),
+            }
+        }
+    }
+
+    eprintln!("checked {checked} answers");
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,128 @@
+use advent_of_code_2023::{
+    days::{
+        day01::Day01,
+        day05::Day05,
+        day06::Day06,
+        day07::{self, Card, Hand, RuleSet, Type},
+        day09::Day09,
+        day19::{self, Day19, Diagnostic},
+        day20::{self, Day20},
+    },
+    solution::{Part2, Solution},
+};
+use aoc_lib::Example;
+use num::{BigInt, BigUint};
+
+fn example<S: Solution>(example: Example, id: u8) -> String {
+    aoc_lib::input(S::DAY).example(example, id).open().unwrap()
+}
+
+#[test]
+fn day01_test() {
+    let data = example::<Day01>(Example::Part1, 1);
+    let parsed = Day01::parse(&data).unwrap();
+    assert_eq!(Day01::part1(&parsed).unwrap(), 142);
+
+    let data = example::<Day01>(Example::Part2, 1);
+    let parsed = Day01::parse(&data).unwrap();
+    assert_eq!(Day01::part2(&parsed).unwrap(), 281);
+}
+
+#[test]
+fn day05_test() {
+    let data = example::<Day05>(Example::Part1, 1);
+    let almanac = Day05::parse(&data).unwrap();
+    assert_eq!(Day05::part1(&almanac).unwrap(), 35);
+    assert_eq!(Day05::part2(&almanac).unwrap(), 46);
+}
+
+#[test]
+fn day06_test() {
+    // Day 6 parses each part its own way, so it only has `SolvePart2`.
+    use advent_of_code_2023::solution::SolvePart2;
+
+    let data = example::<Day06>(Example::Part1, 1);
+    let races = Day06::parse(&data).unwrap();
+    assert_eq!(Day06::part1(&races).unwrap(), 288);
+    let race = Day06::parse_part2(&data).unwrap();
+    assert_eq!(Day06::part2(&race).unwrap(), BigUint::from(71503u32));
+}
+
+#[test]
+fn day09_test() {
+    let data = example::<Day09>(Example::Part1, 1);
+    let sequences = Day09::parse(&data).unwrap();
+    assert_eq!(Day09::part1(&sequences).unwrap(), BigInt::from(114));
+    assert_eq!(Day09::part2(&sequences).unwrap(), BigInt::from(2));
+}
+
+#[test]
+fn day19_test() {
+    let data = example::<Day19>(Example::Part1, 1);
+    let system = Day19::parse(&data).unwrap();
+    assert_eq!(Day19::part1(&system).unwrap(), 19114);
+
+    assert!(Day19::parse("in{x<10:A,foo}\n\n{x=1,m=2,a=3,s=4}").is_err());
+}
+
+#[test]
+fn day05_almanac_test() {
+    let data = example::<Day05>(Example::Part1, 1);
+    let almanac = Day05::parse(&data).unwrap();
+
+    let seeds: Vec<_> = almanac.seeds().collect();
+    assert_eq!(seeds, [79, 14, 55, 13]);
+    assert_eq!(almanac.location_of(79), 82);
+    assert_eq!(almanac.seed_at(82), 79);
+}
+
+#[test]
+fn day07_rules_test() {
+    // Tens are wild and rank lowest.
+    let mut order = day07::STANDARD_ORDER;
+    order[..9].rotate_right(1);
+    let rules = RuleSet::new(order, &[Card::Ten]);
+
+    let hand = Hand([Card::Ten, Card::Ten, Card::Ace, Card::Ace, Card::Two]);
+    assert_eq!(rules.hand_type(hand), Type::FourOfAKind);
+    assert_eq!(day07::PART1_RULES.hand_type(hand), Type::TwoPair);
+}
+
+#[test]
+fn day19_validate_test() {
+    let (workflows, start) = day19::parse_workflows("in{x<10:a,R}\na{x>20:R,x>30:R,A}\nlost{A}");
+    assert_eq!(workflows.names().collect::<Vec<_>>(), ["in", "a", "lost"]);
+
+    let diagnostics = day19::validate(&workflows, start);
+    assert_eq!(
+        diagnostics,
+        [
+            Diagnostic::DeadRule {
+                workflow: "a",
+                rule: 1
+            },
+            Diagnostic::Unreachable { workflow: "lost" },
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "`lost` is unreachable from `in`"
+    );
+
+    let system = Day19::parse("in{x<10:A,R}\n\n{x=1,m=2,a=3,s=4}").unwrap();
+    assert!(system.accepts([9, 0, 0, 0]));
+    assert!(!system.accepts([10, 0, 0, 0]));
+}
+
+#[test]
+fn day20_simulator_test() {
+    let data = example::<Day20>(Example::Part1, 1);
+    let (example, _) = data.split_once("---").unwrap();
+    let system = Day20::parse(example.trim()).unwrap();
+
+    let mut sim = day20::Simulator::new(&system);
+    assert_eq!(sim.press().count(), 12);
+    assert!(sim.is_initial());
+
+    assert_eq!(day20::pulse_product(&system, 1000), Ok(32_000_000));
+}